@@ -0,0 +1,165 @@
+use nostr_sdk::base64::engine::{general_purpose, Engine};
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::upload::DEFAULT_USER_AGENT;
+
+/// How long a Blossom authorization event stays valid (in seconds)
+const AUTH_EXPIRATION_SECS: u64 = 300;
+
+/// Configuration options for Blossom HTTP requests
+#[derive(Debug, Clone)]
+pub struct BlossomConfig {
+    /// Connection timeout duration
+    pub connect_timeout: std::time::Duration,
+    /// Total timeout for a request, including the response body
+    pub timeout: std::time::Duration,
+    /// User-Agent header sent with every request
+    pub user_agent: String,
+}
+
+impl Default for BlossomConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: std::time::Duration::from_secs(5),
+            timeout: std::time::Duration::from_secs(300),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+}
+
+/// Makes a reqwest client configured for Blossom requests
+fn make_client(config: &BlossomConfig) -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .connect_timeout(config.connect_timeout)
+        .timeout(config.timeout)
+        .user_agent(config.user_agent.as_str())
+        .build()
+        .map_err(|e| format!("Failed to build Blossom HTTP client: {e}"))
+}
+
+/// Describes a blob stored on a Blossom server (BUD-02)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlobDescriptor {
+    /// Public URL of the blob
+    pub url: String,
+    /// Hex SHA-256 hash of the blob
+    pub sha256: String,
+    /// Size of the blob in bytes
+    pub size: u64,
+    /// MIME type reported by the server, if any
+    #[serde(rename = "type", default)]
+    pub mime_type: Option<String>,
+    /// Unix timestamp of when the blob was uploaded
+    pub uploaded: u64,
+}
+
+/// Builds a Blossom `Authorization` header for the given action
+///
+/// Signs a kind-24242 event with a `t` tag for the action and a short
+/// expiration, then encodes it as `Nostr <base64 event json>`.
+///
+/// # Arguments
+///
+/// * `signer` - The signer used to sign the authorization event
+/// * `action` - The BUD action verb, e.g. `"list"` or `"upload"`
+/// * `content` - Human readable description of the request
+///
+/// # Returns
+///
+/// A Result containing the header value, or an error message.
+async fn blossom_auth_header<T>(signer: &T, action: &str, content: &str) -> Result<String, String>
+where
+    T: NostrSigner,
+{
+    let expiration = Timestamp::from_secs(Timestamp::now().as_u64() + AUTH_EXPIRATION_SECS);
+    let event = EventBuilder::new(Kind::BlossomAuth, content)
+        .tag(Tag::hashtag(action))
+        .tag(Tag::expiration(expiration))
+        .sign(signer)
+        .await
+        .map_err(|e| format!("Failed to sign Blossom authorization: {e}"))?;
+
+    Ok(format!(
+        "Nostr {}",
+        general_purpose::STANDARD.encode(event.as_json())
+    ))
+}
+
+/// Lists the blobs a public key has stored on a Blossom server
+///
+/// Issues an authorized `GET /list/<pubkey>` request (BUD-02) so a bot can
+/// enumerate its stored attachments for quota management or cleanup.
+///
+/// # Arguments
+///
+/// * `signer` - The signer used to authorize the request
+/// * `server_url` - The base URL of the Blossom server
+/// * `pubkey` - The public key whose blobs should be listed
+///
+/// # Returns
+///
+/// A Result containing the blob descriptors, or an error message.
+pub async fn list_blobs<T>(
+    signer: T,
+    server_url: &Url,
+    pubkey: PublicKey,
+) -> Result<Vec<BlobDescriptor>, String>
+where
+    T: NostrSigner,
+{
+    list_blobs_with_config(signer, server_url, pubkey, None).await
+}
+
+/// Lists the blobs a public key has stored on a Blossom server, with custom timeouts
+///
+/// # Arguments
+///
+/// * `signer` - The signer used to authorize the request
+/// * `server_url` - The base URL of the Blossom server
+/// * `pubkey` - The public key whose blobs should be listed
+/// * `config` - Optional HTTP configuration (defaults to [`BlossomConfig::default`])
+///
+/// # Returns
+///
+/// A Result containing the blob descriptors, or an error message.
+pub async fn list_blobs_with_config<T>(
+    signer: T,
+    server_url: &Url,
+    pubkey: PublicKey,
+    config: Option<BlossomConfig>,
+) -> Result<Vec<BlobDescriptor>, String>
+where
+    T: NostrSigner,
+{
+    let config = config.unwrap_or_default();
+    let list_url = server_url
+        .join(&format!("/list/{}", pubkey.to_hex()))
+        .map_err(|e| format!("Invalid Blossom server URL: {e}"))?;
+
+    let auth = blossom_auth_header(&signer, "list", "List Blobs").await?;
+
+    let client = make_client(&config)?;
+    let response = client
+        .get(list_url)
+        .header("Authorization", auth)
+        .send()
+        .await
+        .map_err(|e| format!("Blossom list request failed: {e}"))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let reason = response
+            .headers()
+            .get("X-Reason")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        return Err(format!("Blossom list failed with status {status}: {reason}"));
+    }
+
+    response
+        .json::<Vec<BlobDescriptor>>()
+        .await
+        .map_err(|e| format!("Failed to decode Blossom list response: {e}"))
+}
@@ -1,25 +1,203 @@
 use log::warn;
 use nostr_sdk::prelude::*;
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+
+/// Relays used when no others are configured.
+pub const DEFAULT_RELAYS: &[&str] = &[
+    "wss://jskitty.cat/nostr",
+    "wss://relay.damus.io",
+    "wss://auth.nostr1.com",
+    "wss://nostr.computingcache.com",
+];
 
 /// Configuration options for the vector client.
+#[derive(Debug, Clone)]
 pub struct ClientConfig {
     /// The address of the proxy server for .onion relays.
     pub proxy_addr: Option<SocketAddr>,
-    /// A list of default relays to connect to.
+    /// Which relays are routed through `proxy_addr`.
+    ///
+    /// With [`ConnectionTarget::Onion`] only `.onion` relays (and `.onion`
+    /// upload servers) use the proxy, while clearnet `wss://` relays connect
+    /// directly. With [`ConnectionTarget::All`] every relay and upload goes
+    /// through the proxy, e.g. to hide the bot's IP behind Tor. Ignored when
+    /// `proxy_addr` is None.
+    pub connection_target: ConnectionTarget,
+    /// A list of default relays to connect to, used for both reading and writing.
     pub default_relays: Vec<String>,
+    /// Relays only read from: subscriptions and fetches, never publishes.
+    pub read_relays: Vec<String>,
+    /// Relays only written to: gift wraps and metadata, never subscriptions.
+    pub write_relays: Vec<String>,
 }
 
+/// The usual address of a local Tor SOCKS5 proxy.
+pub const DEFAULT_TOR_PROXY: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 9050));
+
 impl Default for ClientConfig {
+    /// The [`DEFAULT_RELAYS`] without a proxy, same as `ClientConfig::builder().build()`.
+    fn default() -> Self {
+        ClientConfig::builder().build()
+    }
+}
+
+impl ClientConfig {
+    /// Creates a builder for a ClientConfig.
+    ///
+    /// No proxy is used unless [`ClientConfigBuilder::proxy`] is called, so
+    /// the config works on machines without Tor.
+    ///
+    /// # Returns
+    ///
+    /// A ClientConfigBuilder with no proxy and no relays.
+    pub fn builder() -> ClientConfigBuilder {
+        ClientConfigBuilder::default()
+    }
+}
+
+/// Builder for [`ClientConfig`].
+#[derive(Debug, Clone)]
+pub struct ClientConfigBuilder {
+    proxy_addr: Option<SocketAddr>,
+    connection_target: ConnectionTarget,
+    relays: Vec<String>,
+    read_relays: Vec<String>,
+    write_relays: Vec<String>,
+}
+
+impl Default for ClientConfigBuilder {
     fn default() -> Self {
         Self {
-            proxy_addr: Some(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 9050))),
-            default_relays: vec![
-                "wss://jskitty.cat/nostr".to_string(),
-                "wss://relay.damus.io".to_string(),
-                "wss://auth.nostr1.com".to_string(),
-                "wss://nostr.computingcache.com".to_string(),
-            ],
+            proxy_addr: None,
+            connection_target: ConnectionTarget::Onion,
+            relays: Vec::new(),
+            read_relays: Vec::new(),
+            write_relays: Vec::new(),
+        }
+    }
+}
+
+impl ClientConfigBuilder {
+    /// Adds a relay used for both reading and writing.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The relay URL.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn relay(mut self, url: impl Into<String>) -> Self {
+        self.relays.push(url.into());
+        self
+    }
+
+    /// Adds several relays used for both reading and writing.
+    ///
+    /// # Arguments
+    ///
+    /// * `urls` - The relay URLs.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn relays<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.relays.extend(urls.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds a relay that is only read from.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The relay URL.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn read_relay(mut self, url: impl Into<String>) -> Self {
+        self.read_relays.push(url.into());
+        self
+    }
+
+    /// Adds a relay that is only written to.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The relay URL.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn write_relay(mut self, url: impl Into<String>) -> Self {
+        self.write_relays.push(url.into());
+        self
+    }
+
+    /// Routes connections through a SOCKS5 proxy, such as Tor.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The proxy address, e.g. [`DEFAULT_TOR_PROXY`].
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn proxy(mut self, addr: SocketAddr) -> Self {
+        self.proxy_addr = Some(addr);
+        self
+    }
+
+    /// Connects directly, without a proxy (the default).
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn no_proxy(mut self) -> Self {
+        self.proxy_addr = None;
+        self
+    }
+
+    /// Sets which hosts are routed through the proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - [`ConnectionTarget::Onion`] (default) or [`ConnectionTarget::All`].
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn connection_target(mut self, target: ConnectionTarget) -> Self {
+        self.connection_target = target;
+        self
+    }
+
+    /// Builds the ClientConfig.
+    ///
+    /// # Returns
+    ///
+    /// The ClientConfig, using [`DEFAULT_RELAYS`] if no relay was added.
+    pub fn build(self) -> ClientConfig {
+        let no_relays =
+            self.relays.is_empty() && self.read_relays.is_empty() && self.write_relays.is_empty();
+        let default_relays = if no_relays {
+            DEFAULT_RELAYS.iter().map(|relay| relay.to_string()).collect()
+        } else {
+            self.relays
+        };
+
+        ClientConfig {
+            proxy_addr: self.proxy_addr,
+            connection_target: self.connection_target,
+            default_relays,
+            read_relays: self.read_relays,
+            write_relays: self.write_relays,
         }
     }
 }
@@ -44,6 +222,7 @@ impl Default for ClientConfig {
 /// # Returns
 ///
 /// A configured vector client.
+#[allow(clippy::too_many_arguments)]
 pub async fn build_client(
     keys: Keys,
     name: String,
@@ -55,18 +234,97 @@ pub async fn build_client(
     lud16: String,
     config: Option<ClientConfig>,
 ) -> Client {
+    // Set up metadata
+    let metadata = crate::metadata::create_metadata(
+        name,
+        display_name,
+        about,
+        Some(picture),
+        Some(banner),
+        Some(nip05),
+        Some(lud16),
+    );
+
+    build_client_with_metadata(keys, &metadata, config).await
+}
+
+/// Configures and builds a vector client with pre-built metadata.
+///
+/// Same as [`build_client`], but takes a [`Metadata`] object so optional
+/// profile fields can be left out.
+///
+/// # Arguments
+///
+/// * `keys` - The keys used to sign messages.
+/// * `metadata` - The metadata to publish for the bot.
+/// * `config` - Optional client configuration.
+///
+/// # Returns
+///
+/// A configured vector client.
+pub async fn build_client_with_metadata(
+    keys: Keys,
+    metadata: &Metadata,
+    config: Option<ClientConfig>,
+) -> Client {
+    let public_key = keys.public_key();
+    build_client_with_signer(Arc::new(keys), public_key, metadata, config).await
+}
+
+/// Configures and builds a vector client around any signer.
+///
+/// Same as [`build_client_with_metadata`], but accepts a remote (NIP-46)
+/// signer or any other [`NostrSigner`] instead of local keys.
+///
+/// # Arguments
+///
+/// * `signer` - The signer used for events and encryption.
+/// * `public_key` - The signer's public key, used for the gift wrap subscription.
+/// * `metadata` - The metadata to publish for the bot.
+/// * `config` - Optional client configuration.
+///
+/// # Returns
+///
+/// A configured vector client.
+pub async fn build_client_with_signer(
+    signer: Arc<dyn NostrSigner>,
+    public_key: PublicKey,
+    metadata: &Metadata,
+    config: Option<ClientConfig>,
+) -> Client {
+    let client = create_client(signer, config).await;
+    if let Err(e) = connect_client(&client, public_key, metadata).await {
+        warn!("Failed to set up client: {e:?}");
+    }
+    client
+}
+
+/// Creates a vector client with its relays added, without any network I/O.
+///
+/// Call [`connect_client`] afterwards to connect, publish metadata and
+/// subscribe to gift wraps.
+///
+/// # Arguments
+///
+/// * `signer` - The signer used for events and encryption.
+/// * `config` - Optional client configuration.
+///
+/// # Returns
+///
+/// An unconnected vector client.
+pub async fn create_client(signer: Arc<dyn NostrSigner>, config: Option<ClientConfig>) -> Client {
     let config = config.unwrap_or_default();
 
     // Create new client with default options
-    let mut client = Client::builder().signer(keys.clone()).build();
+    let mut client = Client::builder().signer(signer.clone()).build();
 
     // Configure proxy if provided
     if let Some(proxy_addr) = config.proxy_addr {
         let connection = Connection::new()
             .proxy(proxy_addr) // Use `.embedded_tor()` instead to enable the embedded tor client (require `tor` feature)
-            .target(ConnectionTarget::Onion);
+            .target(config.connection_target);
         let opts = Options::new().connection(connection);
-        client = Client::builder().signer(keys.clone()).opts(opts).build();
+        client = Client::builder().signer(signer).opts(opts).build();
     }
 
     // Add default relays
@@ -76,28 +334,75 @@ pub async fn build_client(
         }
     }
 
+    // Add read-only and write-only relays
+    for relay in &config.read_relays {
+        if let Err(e) = client.add_read_relay(relay).await {
+            warn!("Failed to add read relay {relay}: {e:?}");
+        }
+    }
+    for relay in &config.write_relays {
+        if let Err(e) = client.add_write_relay(relay).await {
+            warn!("Failed to add write relay {relay}: {e:?}");
+        }
+    }
+
+    client
+}
+
+/// Connects a client to its relays, publishes metadata and subscribes to gift wraps.
+///
+/// # Arguments
+///
+/// * `client` - A client created with [`create_client`].
+/// * `public_key` - The bot's public key, used for the gift wrap subscription.
+/// * `metadata` - The metadata to publish for the bot.
+///
+/// # Returns
+///
+/// A Result that is an error if the metadata could not be published or the
+/// subscription could not be created.
+pub async fn connect_client(
+    client: &Client,
+    public_key: PublicKey,
+    metadata: &Metadata,
+) -> Result<(), nostr_sdk::client::Error> {
+    connect_client_with_metadata_event(client, public_key, EventBuilder::metadata(metadata)).await
+}
+
+/// Same as [`connect_client`], but publishes a prepared metadata event so
+/// callers can add tags to it.
+pub(crate) async fn connect_client_with_metadata_event(
+    client: &Client,
+    public_key: PublicKey,
+    metadata: EventBuilder,
+) -> Result<(), nostr_sdk::client::Error> {
     // Connect to relays
     client.connect().await;
 
-    // Set up metadata
-    let metadata = crate::metadata::create_metadata(
-        name,
-        display_name,
-        about,
-        Some(picture),
-        Some(banner),
-        Some(nip05),
-        Some(lud16),
-    );
-
-    // Update metadata
-    let _ = client.set_metadata(&metadata).await;
+    // Update metadata, still subscribing if no relay accepts it
+    let published = client.send_event_builder(metadata).await;
 
     // Set up subscription for gift wrap events
-    let subscription =
-        crate::subscription::create_gift_wrap_subscription(keys.public_key(), None, None).unwrap();
+    subscribe_gift_wraps(client, public_key).await?;
+
+    published.map(|_| ())
+}
 
-    let _ = client.subscribe(subscription, None).await;
+/// Subscription id of the bot's gift wrap subscription
+const GIFT_WRAP_SUBSCRIPTION_ID: &str = "vector-gift-wraps";
 
-    client
+/// Subscribes to gift wraps for the bot.
+///
+/// A fixed subscription id is used, so calling this again after a reconnect
+/// replaces the subscription instead of adding a duplicate.
+pub(crate) async fn subscribe_gift_wraps(
+    client: &Client,
+    public_key: PublicKey,
+) -> Result<(), nostr_sdk::client::Error> {
+    let subscription =
+        crate::subscription::create_gift_wrap_subscription(public_key, None, None).unwrap();
+    let id = SubscriptionId::new(GIFT_WRAP_SUBSCRIPTION_ID);
+
+    client.subscribe_with_id(id, subscription, None).await?;
+    Ok(())
 }
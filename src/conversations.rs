@@ -0,0 +1,216 @@
+use crate::storage::{Storage, StorageError, CONVERSATIONS_STORE, UNREAD_STORE};
+use log::warn;
+use nostr_sdk::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Most unread messages remembered per counterparty.
+///
+/// Older ones are dropped first, which also keeps a single batched read
+/// receipt well below relay size limits.
+pub const MAX_UNREAD_PER_PEER: usize = 250;
+
+/// Most counterparties tracked at once.
+///
+/// Past this, the one with the oldest last message is forgotten, so a flood
+/// of messages from throwaway keys cannot grow the index without bound.
+pub const MAX_CONVERSATIONS: usize = 10_000;
+
+/// The counterparties the bot has exchanged messages with.
+///
+/// Tracks the timestamp of the latest message sent to or received from each
+/// public key, and which received messages have not been marked read yet.
+/// When a [`Storage`] backend is configured, entries are persisted so the
+/// conversation list survives restarts. At most [`MAX_CONVERSATIONS`]
+/// counterparties are kept.
+#[derive(Debug, Default)]
+pub struct ConversationIndex {
+    last_message: HashMap<PublicKey, Timestamp>,
+    unread: HashMap<PublicKey, Vec<EventId>>,
+    storage: Option<Arc<dyn Storage>>,
+}
+
+impl ConversationIndex {
+    /// Creates a new in-memory ConversationIndex.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a ConversationIndex backed by a [`Storage`] implementation.
+    ///
+    /// Entries are kept in the [`CONVERSATIONS_STORE`] and [`UNREAD_STORE`] stores.
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - The backend to load from and write to.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the loaded ConversationIndex.
+    pub fn with_storage(storage: Arc<dyn Storage>) -> Result<Self, StorageError> {
+        let mut index = Self::new();
+
+        for (key, value) in storage.list(CONVERSATIONS_STORE)? {
+            let pubkey = PublicKey::from_hex(&key);
+            let timestamp = value.try_into().map(u64::from_be_bytes);
+            match (pubkey, timestamp) {
+                (Ok(pubkey), Ok(timestamp)) => {
+                    index.last_message.insert(pubkey, Timestamp::from_secs(timestamp));
+                }
+                _ => storage.remove(CONVERSATIONS_STORE, &key)?,
+            }
+        }
+
+        for (key, value) in storage.list(UNREAD_STORE)? {
+            let pubkey = PublicKey::from_hex(&key);
+            let ids: Result<Vec<EventId>, _> =
+                value.chunks(EventId::LEN).map(EventId::from_slice).collect();
+            match (pubkey, ids) {
+                (Ok(pubkey), Ok(mut ids)) if !ids.is_empty() => {
+                    let excess = ids.len().saturating_sub(MAX_UNREAD_PER_PEER);
+                    ids.drain(..excess);
+                    index.unread.insert(pubkey, ids);
+                }
+                _ => storage.remove(UNREAD_STORE, &key)?,
+            }
+        }
+
+        index.storage = Some(storage);
+        index.evict_excess(None);
+        Ok(index)
+    }
+
+    /// Records a message exchanged with a counterparty.
+    ///
+    /// Older timestamps than the one already recorded are ignored, so
+    /// out-of-order deliveries do not move a conversation back in time.
+    ///
+    /// # Arguments
+    ///
+    /// * `pubkey` - The other side of the conversation.
+    /// * `timestamp` - When the message was created.
+    pub fn record(&mut self, pubkey: PublicKey, timestamp: Timestamp) {
+        let last = self.last_message.entry(pubkey).or_insert(timestamp);
+        if *last > timestamp {
+            return;
+        }
+        *last = timestamp;
+
+        if let Some(ref storage) = self.storage {
+            let value = timestamp.as_u64().to_be_bytes();
+            if let Err(e) = storage.set(CONVERSATIONS_STORE, &pubkey.to_hex(), &value) {
+                warn!("Failed to persist conversation: {e}");
+            }
+        }
+        self.evict_excess(Some(&pubkey));
+    }
+
+    /// Records a received message that has not been read yet.
+    ///
+    /// Once a counterparty has [`MAX_UNREAD_PER_PEER`] unread messages, the
+    /// oldest one is forgotten to make room.
+    ///
+    /// # Arguments
+    ///
+    /// * `pubkey` - The sender of the message.
+    /// * `event_id` - The id of the message rumor.
+    pub fn record_unread(&mut self, pubkey: PublicKey, event_id: EventId) {
+        let unread = self.unread.entry(pubkey).or_default();
+        if unread.contains(&event_id) {
+            return;
+        }
+        if unread.len() >= MAX_UNREAD_PER_PEER {
+            unread.remove(0);
+        }
+        unread.push(event_id);
+        self.persist_unread(&pubkey);
+        self.evict_excess(Some(&pubkey));
+    }
+
+    /// Returns the unread messages from a counterparty, oldest first.
+    pub fn unread(&self, pubkey: &PublicKey) -> Vec<EventId> {
+        self.unread.get(pubkey).cloned().unwrap_or_default()
+    }
+
+    /// Marks messages from a counterparty as read.
+    ///
+    /// # Arguments
+    ///
+    /// * `pubkey` - The sender of the messages.
+    /// * `event_ids` - The messages that were read.
+    pub fn clear_unread(&mut self, pubkey: &PublicKey, event_ids: &[EventId]) {
+        let Some(unread) = self.unread.get_mut(pubkey) else {
+            return;
+        };
+        unread.retain(|id| !event_ids.contains(id));
+        if unread.is_empty() {
+            self.unread.remove(pubkey);
+        }
+        self.persist_unread(pubkey);
+    }
+
+    /// Forgets counterparties, oldest last message first, until at most
+    /// [`MAX_CONVERSATIONS`] are tracked. `keep` is never evicted.
+    fn evict_excess(&mut self, keep: Option<&PublicKey>) {
+        let over_cap =
+            |index: &Self| index.last_message.len().max(index.unread.len()) > MAX_CONVERSATIONS;
+        while over_cap(self) {
+            // Peers with unread messages but no recorded time go first
+            let oldest = self
+                .last_message
+                .keys()
+                .chain(self.unread.keys())
+                .filter(|pubkey| Some(*pubkey) != keep)
+                .min_by_key(|pubkey| self.last_message.get(pubkey).copied())
+                .copied();
+            let Some(oldest) = oldest else {
+                return;
+            };
+            self.forget(&oldest);
+        }
+    }
+
+    /// Removes a counterparty, and its stored entries, from the index.
+    fn forget(&mut self, pubkey: &PublicKey) {
+        self.last_message.remove(pubkey);
+        self.unread.remove(pubkey);
+
+        let Some(ref storage) = self.storage else {
+            return;
+        };
+        let key = pubkey.to_hex();
+        for store in [CONVERSATIONS_STORE, UNREAD_STORE] {
+            if let Err(e) = storage.remove(store, &key) {
+                warn!("Failed to forget conversation: {e}");
+            }
+        }
+    }
+
+    /// Writes the unread messages of a counterparty to storage, if configured.
+    fn persist_unread(&self, pubkey: &PublicKey) {
+        let Some(ref storage) = self.storage else {
+            return;
+        };
+        let result = match self.unread.get(pubkey) {
+            Some(ids) => {
+                let value: Vec<u8> = ids.iter().flat_map(|id| id.to_bytes()).collect();
+                storage.set(UNREAD_STORE, &pubkey.to_hex(), &value)
+            }
+            None => storage.remove(UNREAD_STORE, &pubkey.to_hex()),
+        };
+        if let Err(e) = result {
+            warn!("Failed to persist unread messages: {e}");
+        }
+    }
+
+    /// Returns every counterparty with its last message time, newest first.
+    pub fn list(&self) -> Vec<(PublicKey, Timestamp)> {
+        let mut conversations: Vec<(PublicKey, Timestamp)> = self
+            .last_message
+            .iter()
+            .map(|(pubkey, timestamp)| (*pubkey, *timestamp))
+            .collect();
+        conversations.sort_unstable_by_key(|(_, timestamp)| std::cmp::Reverse(*timestamp));
+        conversations
+    }
+}
@@ -1,21 +1,49 @@
 use aes::Aes256;
-use aes_gcm::{AeadInPlace, AesGcm, Error as AesGcmError, KeyInit};
-use generic_array::{typenum::U16, GenericArray};
+use aes_gcm::{AeadInPlace, AesGcm, Error as AesGcmError, KeyInit, TagSize};
+use generic_array::typenum::{U12, U13, U14, U15, U16};
+use generic_array::GenericArray;
 use log::debug;
-use rand::Rng;
+use nostr_sdk::nips::nip44;
+use nostr_sdk::{Keys, PublicKey, SecretKey};
+use rand::{CryptoRng, Rng, RngCore};
 use thiserror::Error;
 // Removed unused import
 
+/// Length of an AES-256 key in bytes
+const KEY_SIZE: usize = 32;
+
+/// Length of the AES-GCM nonce in bytes (16 to match 0xChat)
+const NONCE_SIZE: usize = 16;
+
+/// Default length of the AES-GCM authentication tag appended to the ciphertext
+pub const DEFAULT_TAG_SIZE: usize = 16;
+
+/// Shortest authentication tag AES-GCM supports
+const MIN_TAG_SIZE: usize = 12;
+
 /// Represents encryption parameters for AES-256-GCM
 ///
 /// This struct contains the encryption key and initialization vector (nonce)
 /// needed for AES-256-GCM encryption.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct EncryptionParams {
     /// The encryption key (hex string)
     pub key: String,
     /// The initialization vector (nonce) (hex string)
     pub nonce: String,
+    /// Length of the authentication tag in bytes, from 12 to 16
+    ///
+    /// Only peers that truncate tags need anything but the default of 16.
+    #[serde(default = "default_tag_size", skip_serializing_if = "is_default_tag_size")]
+    pub tag_size: usize,
+}
+
+fn default_tag_size() -> usize {
+    DEFAULT_TAG_SIZE
+}
+
+fn is_default_tag_size(tag_size: &usize) -> bool {
+    *tag_size == DEFAULT_TAG_SIZE
 }
 
 /// Errors that can occur during encryption/decryption operations
@@ -33,6 +61,10 @@ pub enum CryptoError {
     #[error("AES-GCM encryption error: {0}")]
     AesGcmError(String),
 
+    /// NIP-44 encryption or decryption error
+    #[error("NIP-44 error: {0}")]
+    Nip44Error(String),
+
     /// Generic error with message
     #[error("{0}")]
     GenericError(String),
@@ -54,16 +86,33 @@ impl From<AesGcmError> for CryptoError {
 ///
 /// An EncryptionParams struct containing the generated key and nonce.
 pub fn generate_encryption_params() -> Result<EncryptionParams, CryptoError> {
-    let mut rng = rand::thread_rng();
+    generate_encryption_params_with_rng(&mut rand::thread_rng())
+}
 
+/// Generates encryption parameters (key and nonce) from the given RNG
+///
+/// Same as [`generate_encryption_params`], but lets callers supply the RNG,
+/// e.g. a seeded one so tests can assert exact ciphertext.
+///
+/// # Arguments
+///
+/// * `rng` - The cryptographically secure RNG to draw the key and nonce from
+///
+/// # Returns
+///
+/// An EncryptionParams struct containing the generated key and nonce.
+pub fn generate_encryption_params_with_rng<R: RngCore + CryptoRng>(
+    rng: &mut R,
+) -> Result<EncryptionParams, CryptoError> {
     // Generate 32 byte key (for AES-256)
-    let key = rng.gen::<[u8; 32]>();
+    let key = rng.gen::<[u8; KEY_SIZE]>();
     // Generate 16 byte nonce (to match 0xChat)
-    let nonce = rng.gen::<[u8; 16]>();
+    let nonce = rng.gen::<[u8; NONCE_SIZE]>();
 
     Ok(EncryptionParams {
         key: hex::encode(key),
         nonce: hex::encode(nonce),
+        tag_size: DEFAULT_TAG_SIZE,
     })
 }
 
@@ -83,31 +132,284 @@ pub fn generate_encryption_params() -> Result<EncryptionParams, CryptoError> {
 /// A Result containing the encrypted data with the authentication tag appended,
 /// or a CryptoError if encryption fails.
 pub fn encrypt_data(data: &[u8], params: &EncryptionParams) -> Result<Vec<u8>, CryptoError> {
-    debug!("Encrypting data with key: {}", params.key);
+    encrypt_data_with_aad(data, params, &[])
+}
+
+/// Returns the size of the ciphertext produced for a plaintext of the given length
+///
+/// [`encrypt_data`] appends the GCM authentication tag and nothing else, so
+/// UIs can show the on-wire size and enforce upload quotas before encrypting.
+/// Assumes the default tag size, which is what generated params use.
+///
+/// # Arguments
+///
+/// * `plaintext_len` - The length of the data to be encrypted
+///
+/// # Returns
+///
+/// The length of the encrypted data.
+pub fn encrypted_size(plaintext_len: usize) -> usize {
+    plaintext_len + DEFAULT_TAG_SIZE
+}
+
+/// Encrypts data using AES-256-GCM, authenticating additional data
+///
+/// The associated data is not encrypted or stored, but decryption fails
+/// unless the exact same bytes are supplied again.
+///
+/// # Arguments
+///
+/// * `data` - The data to encrypt
+/// * `params` - The encryption parameters containing the key and nonce
+/// * `aad` - Associated data bound to the ciphertext (empty for none)
+///
+/// # Returns
+///
+/// A Result containing the encrypted data with the authentication tag appended,
+/// or a CryptoError if encryption fails.
+pub fn encrypt_data_with_aad(
+    data: &[u8],
+    params: &EncryptionParams,
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    debug!("Encrypting {} bytes", data.len());
 
     // Decode key and nonce from hex
+    let (key_bytes, nonce_bytes) = decode_params(params)?;
+
+    // Encrypt a copy and append the authentication tag
+    let mut buffer = data.to_vec();
+    match params.tag_size {
+        12 => seal::<U12>(&key_bytes, &nonce_bytes, aad, &mut buffer)?,
+        13 => seal::<U13>(&key_bytes, &nonce_bytes, aad, &mut buffer)?,
+        14 => seal::<U14>(&key_bytes, &nonce_bytes, aad, &mut buffer)?,
+        15 => seal::<U15>(&key_bytes, &nonce_bytes, aad, &mut buffer)?,
+        _ => seal::<U16>(&key_bytes, &nonce_bytes, aad, &mut buffer)?,
+    }
+
+    debug!("Data encrypted successfully");
+    Ok(buffer)
+}
+
+/// Encrypts `buffer` in place and appends a tag of `T` bytes
+fn seal<T: TagSize>(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    buffer: &mut Vec<u8>,
+) -> Result<(), CryptoError> {
+    let cipher = AesGcm::<Aes256, U16, T>::new(GenericArray::from_slice(key));
+    let tag = cipher
+        .encrypt_in_place_detached(GenericArray::from_slice(nonce), aad, buffer)
+        .map_err(|e| CryptoError::AesGcmError(e.to_string()))?;
+    buffer.extend_from_slice(tag.as_slice());
+    Ok(())
+}
+
+/// Splits off a tag of `T` bytes and decrypts the rest
+fn open<T: TagSize>(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    data: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let cipher = AesGcm::<Aes256, U16, T>::new(GenericArray::from_slice(key));
+    let (ciphertext, tag) = data.split_at(data.len() - T::USIZE);
+    let mut buffer = ciphertext.to_vec();
+    cipher.decrypt_in_place_detached(
+        GenericArray::from_slice(nonce),
+        aad,
+        &mut buffer,
+        GenericArray::from_slice(tag),
+    )?;
+    Ok(buffer)
+}
+
+/// Decodes the hex key and nonce, checking their lengths
+///
+/// `GenericArray::from_slice` panics on a length mismatch, and the params may
+/// come from a received rumor, so the lengths are validated up front.
+fn decode_params(params: &EncryptionParams) -> Result<(Vec<u8>, Vec<u8>), CryptoError> {
     let key_bytes = hex::decode(&params.key)
         .map_err(|_| CryptoError::HexEncodingError("Invalid key".into()))?;
     let nonce_bytes = hex::decode(&params.nonce)
         .map_err(|_| CryptoError::HexEncodingError("Invalid nonce".into()))?;
 
-    // Initialize AES-GCM cipher
-    let cipher = AesGcm::<Aes256, U16>::new(GenericArray::from_slice(&key_bytes));
+    if key_bytes.len() != KEY_SIZE {
+        return Err(CryptoError::GenericError(format!("key must be {KEY_SIZE} bytes")));
+    }
+    if nonce_bytes.len() != NONCE_SIZE {
+        return Err(CryptoError::GenericError(format!("nonce must be {NONCE_SIZE} bytes")));
+    }
+    if !(MIN_TAG_SIZE..=DEFAULT_TAG_SIZE).contains(&params.tag_size) {
+        return Err(CryptoError::GenericError(format!(
+            "tag size must be {MIN_TAG_SIZE} to {DEFAULT_TAG_SIZE} bytes, got {}",
+            params.tag_size
+        )));
+    }
 
-    // Prepare nonce
-    let nonce = GenericArray::from_slice(&nonce_bytes);
+    Ok((key_bytes, nonce_bytes))
+}
 
-    // Create output buffer
-    let mut buffer = data.to_vec();
+/// Decrypts data produced by [`encrypt_data`]
+///
+/// # Arguments
+///
+/// * `data` - The encrypted data with the authentication tag appended
+/// * `params` - The encryption parameters containing the key and nonce
+///
+/// # Returns
+///
+/// A Result containing the decrypted data, or a CryptoError if the data was
+/// tampered with or the parameters are wrong.
+pub fn decrypt_data(data: &[u8], params: &EncryptionParams) -> Result<Vec<u8>, CryptoError> {
+    decrypt_data_with_aad(data, params, &[])
+}
 
-    // Encrypt in place and get authentication tag
-    let tag = cipher
-        .encrypt_in_place_detached(nonce, &[], &mut buffer)
-        .map_err(|e| CryptoError::AesGcmError(e.to_string()))?;
+/// Decrypts data produced by [`encrypt_data_with_aad`]
+///
+/// # Arguments
+///
+/// * `data` - The encrypted data with the authentication tag appended
+/// * `params` - The encryption parameters containing the key and nonce
+/// * `aad` - The associated data used during encryption
+///
+/// # Returns
+///
+/// A Result containing the decrypted data, or a CryptoError if the data or
+/// associated data was tampered with.
+pub fn decrypt_data_with_aad(
+    data: &[u8],
+    params: &EncryptionParams,
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let (key_bytes, nonce_bytes) = decode_params(params)?;
+    if data.len() < params.tag_size {
+        return Err(CryptoError::GenericError(format!(
+            "Ciphertext is {} bytes, shorter than the {}-byte tag",
+            data.len(),
+            params.tag_size
+        )));
+    }
 
-    // Append the authentication tag to the encrypted data
-    buffer.extend_from_slice(tag.as_slice());
+    // Split off the authentication tag appended by encryption
+    match params.tag_size {
+        12 => open::<U12>(&key_bytes, &nonce_bytes, aad, data),
+        13 => open::<U13>(&key_bytes, &nonce_bytes, aad, data),
+        14 => open::<U14>(&key_bytes, &nonce_bytes, aad, data),
+        15 => open::<U15>(&key_bytes, &nonce_bytes, aad, data),
+        _ => open::<U16>(&key_bytes, &nonce_bytes, aad, data),
+    }
+}
 
-    debug!("Data encrypted successfully");
-    Ok(buffer)
+/// Rumor tag announcing that an attachment was encrypted with associated data
+pub const ATTACHMENT_AAD_TAG: &str = "encryption-aad";
+
+/// Value of [`ATTACHMENT_AAD_TAG`] naming the tags bound by [`attachment_aad`]
+pub const ATTACHMENT_AAD_VALUE: &str = "ox:file-type";
+
+/// Builds the associated data that binds an attachment's metadata to its ciphertext
+///
+/// Combines the plaintext hash (`ox` tag) and MIME type (`file-type` tag), so
+/// swapping either tag in a rumor makes decryption fail.
+///
+/// # Arguments
+///
+/// * `file_hash` - The hex SHA-256 of the plaintext file
+/// * `mime_type` - The MIME type of the file
+///
+/// # Returns
+///
+/// The associated data bytes.
+pub fn attachment_aad(file_hash: &str, mime_type: &str) -> Vec<u8> {
+    format!("{file_hash}:{mime_type}").into_bytes()
+}
+
+/// Encrypts attachment encryption parameters to a recipient with NIP-44
+///
+/// The key and nonce are serialized as JSON and encrypted into a single
+/// payload, so they never appear as discrete plaintext tags in the rumor.
+///
+/// # Arguments
+///
+/// * `secret_key` - The sender's secret key
+/// * `recipient` - The recipient's public key
+/// * `params` - The encryption parameters to protect
+///
+/// # Returns
+///
+/// A Result containing the base64 NIP-44 payload, or a CryptoError.
+pub fn encrypt_params_nip44(
+    secret_key: &SecretKey,
+    recipient: &PublicKey,
+    params: &EncryptionParams,
+) -> Result<String, CryptoError> {
+    let json = serde_json::to_string(params)
+        .map_err(|e| CryptoError::GenericError(e.to_string()))?;
+    nip44::encrypt(secret_key, recipient, json, nip44::Version::V2)
+        .map_err(|e| CryptoError::Nip44Error(e.to_string()))
+}
+
+/// Decrypts attachment encryption parameters produced by [`encrypt_params_nip44`]
+///
+/// # Arguments
+///
+/// * `secret_key` - The recipient's secret key
+/// * `sender` - The sender's public key
+/// * `payload` - The base64 NIP-44 payload from the rumor
+///
+/// # Returns
+///
+/// A Result containing the decrypted EncryptionParams, or a CryptoError.
+pub fn decrypt_params_nip44(
+    secret_key: &SecretKey,
+    sender: &PublicKey,
+    payload: &str,
+) -> Result<EncryptionParams, CryptoError> {
+    let json = nip44::decrypt(secret_key, sender, payload)
+        .map_err(|e| CryptoError::Nip44Error(e.to_string()))?;
+    serde_json::from_str(&json).map_err(|e| CryptoError::GenericError(e.to_string()))
+}
+
+/// Encrypts a text payload directly with NIP-44 v2
+///
+/// For integrations that want a raw NIP-44 payload rather than a
+/// gift-wrapped message. The result can be read with [`nip44_decrypt`].
+///
+/// # Arguments
+///
+/// * `sender` - The sender's keys
+/// * `recipient` - The recipient's public key
+/// * `plaintext` - The text to encrypt
+///
+/// # Returns
+///
+/// A Result containing the base64 NIP-44 payload, or a CryptoError.
+pub fn nip44_encrypt(
+    sender: &Keys,
+    recipient: &PublicKey,
+    plaintext: &str,
+) -> Result<String, CryptoError> {
+    nip44::encrypt(sender.secret_key(), recipient, plaintext, nip44::Version::V2)
+        .map_err(|e| CryptoError::Nip44Error(e.to_string()))
+}
+
+/// Decrypts a NIP-44 payload produced by [`nip44_encrypt`]
+///
+/// # Arguments
+///
+/// * `recipient` - The recipient's keys
+/// * `sender` - The sender's public key
+/// * `payload` - The base64 NIP-44 payload
+///
+/// # Returns
+///
+/// A Result containing the decrypted text, or a CryptoError.
+pub fn nip44_decrypt(
+    recipient: &Keys,
+    sender: &PublicKey,
+    payload: &str,
+) -> Result<String, CryptoError> {
+    nip44::decrypt(recipient.secret_key(), sender, payload)
+        .map_err(|e| CryptoError::Nip44Error(e.to_string()))
 }
@@ -0,0 +1,202 @@
+use crate::storage::{Storage, StorageError, SEEN_EVENTS_STORE};
+use log::warn;
+use lru::LruCache;
+use nostr_sdk::prelude::*;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Default number of event ids remembered by a [`SeenCache`].
+pub const DEFAULT_SEEN_CACHE_CAPACITY: usize = 10_000;
+
+/// An LRU cache of event ids the bot has already handled.
+///
+/// Gift wraps are re-delivered whenever the client reconnects, so the
+/// notification loop uses this cache to skip events it has already processed.
+/// When a path or [`Storage`] backend is configured, ids are persisted so
+/// restarts do not reprocess old messages either.
+#[derive(Debug)]
+pub struct SeenCache {
+    entries: LruCache<EventId, ()>,
+    path: Option<PathBuf>,
+    storage: Option<Arc<dyn Storage>>,
+    /// Number of ids currently written to `path`, including evicted ones
+    file_lines: usize,
+    /// Insertion counter stored with each id, so loading keeps the newest ids
+    seq: u64,
+}
+
+impl SeenCache {
+    /// Creates a new in-memory SeenCache.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of event ids to remember (minimum 1).
+    ///
+    /// # Returns
+    ///
+    /// An empty SeenCache.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: LruCache::new(capacity),
+            path: None,
+            storage: None,
+            file_lines: 0,
+            seq: 0,
+        }
+    }
+
+    /// Creates a SeenCache backed by a file on disk.
+    ///
+    /// Previously persisted ids are loaded, and the file is compacted to the
+    /// cache's capacity. New ids are appended, and the file is compacted again
+    /// once evicted ids make up half of it, so it never grows past twice the
+    /// capacity.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of event ids to remember (minimum 1).
+    /// * `path` - The file to load from and append to.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the loaded SeenCache.
+    pub fn with_persistence<P: AsRef<Path>>(capacity: usize, path: P) -> std::io::Result<Self> {
+        let mut cache = Self::new(capacity);
+        let path = path.as_ref().to_path_buf();
+
+        if path.exists() {
+            let reader = BufReader::new(File::open(&path)?);
+            for line in reader.lines() {
+                let line = line?;
+                if let Ok(id) = EventId::from_hex(line.trim()) {
+                    cache.entries.put(id, ());
+                }
+            }
+        }
+
+        cache.compact(&path)?;
+        cache.path = Some(path);
+        Ok(cache)
+    }
+
+    /// Rewrites the file with only the retained ids, oldest first.
+    fn compact(&mut self, path: &Path) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        for (id, _) in self.entries.iter().rev() {
+            writeln!(file, "{}", id.to_hex())?;
+        }
+        self.file_lines = self.entries.len();
+        Ok(())
+    }
+
+    /// Creates a SeenCache backed by a [`Storage`] implementation.
+    ///
+    /// Ids are kept in the [`SEEN_EVENTS_STORE`] store. Ids beyond the cache's
+    /// capacity are removed from the backend, both on load and as they are
+    /// evicted.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of event ids to remember (minimum 1).
+    /// * `storage` - The backend to load from and write to.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the loaded SeenCache.
+    pub fn with_storage(capacity: usize, storage: Arc<dyn Storage>) -> Result<Self, StorageError> {
+        let mut cache = Self::new(capacity);
+
+        let mut stored: Vec<(u64, String)> = storage
+            .list(SEEN_EVENTS_STORE)?
+            .into_iter()
+            .map(|(key, value)| {
+                let seq = value.try_into().map(u64::from_be_bytes).unwrap_or_default();
+                (seq, key)
+            })
+            .collect();
+        stored.sort_unstable();
+
+        // Insert oldest first so the LRU keeps the newest ids
+        for (seq, key) in stored {
+            cache.seq = cache.seq.max(seq);
+            match EventId::from_hex(&key) {
+                Ok(id) => {
+                    if let Some((evicted, _)) = cache.entries.push(id, ()) {
+                        storage.remove(SEEN_EVENTS_STORE, &evicted.to_hex())?;
+                    }
+                }
+                Err(_) => storage.remove(SEEN_EVENTS_STORE, &key)?,
+            }
+        }
+
+        cache.storage = Some(storage);
+        Ok(cache)
+    }
+
+    /// Records an event id as seen.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The event id to record.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the id was already in the cache, `false` if it is new.
+    pub fn check_and_insert(&mut self, id: EventId) -> bool {
+        if self.entries.contains(&id) {
+            self.entries.promote(&id);
+            return true;
+        }
+        let evicted = self.entries.push(id, ());
+
+        if let Some(ref storage) = self.storage {
+            self.seq += 1;
+            let stored = storage
+                .set(SEEN_EVENTS_STORE, &id.to_hex(), &self.seq.to_be_bytes())
+                .and_then(|()| match evicted {
+                    Some((old, _)) => storage.remove(SEEN_EVENTS_STORE, &old.to_hex()),
+                    None => Ok(()),
+                });
+            if let Err(e) = stored {
+                warn!("Failed to persist seen event id: {e}");
+            }
+        }
+
+        if let Some(path) = self.path.clone() {
+            let written = if self.file_lines >= 2 * self.entries.cap().get() {
+                self.compact(&path)
+            } else {
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .and_then(|mut file| writeln!(file, "{}", id.to_hex()))
+                    .map(|()| self.file_lines += 1)
+            };
+            if let Err(e) = written {
+                warn!("Failed to persist seen event id: {e}");
+            }
+        }
+
+        false
+    }
+
+    /// Returns whether the event id has been seen, without recording it.
+    pub fn contains(&self, id: &EventId) -> bool {
+        self.entries.contains(id)
+    }
+
+    /// Returns the number of remembered event ids.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,153 @@
+use crate::upload::{make_client, ProgressCallback, UploadError};
+use futures_util::StreamExt;
+use log::debug;
+use nostr_sdk::Url;
+use std::net::SocketAddr;
+use std::time::Duration;
+use thiserror::Error;
+
+/// How long a download may go without receiving any bytes before it is aborted
+const DOWNLOAD_STALL_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Largest buffer reserved up front from a server-reported `Content-Length`
+const MAX_PREALLOCATION: u64 = 8 * 1024 * 1024;
+
+/// Default limit for a single download (100 MiB)
+pub const DEFAULT_MAX_DOWNLOAD_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Errors that can occur during download operations
+#[derive(Error, Debug)]
+pub enum DownloadError {
+    /// Reqwest client error
+    #[error("Reqwest client error: {0}")]
+    ReqwestError(#[from] reqwest::Error),
+
+    /// The server answered with a non-success status
+    #[error("Download failed with status {0}")]
+    HttpStatus(reqwest::StatusCode),
+
+    /// No bytes arrived within the stall timeout
+    #[error("Download stalled - no progress detected")]
+    Stalled,
+
+    /// The body is larger than the allowed maximum, in bytes
+    #[error("Download exceeds the {0} byte limit")]
+    TooLarge(u64),
+
+    /// Generic error with message
+    #[error("{0}")]
+    GenericError(String),
+}
+
+impl From<UploadError> for DownloadError {
+    fn from(err: UploadError) -> Self {
+        match err {
+            UploadError::ReqwestError(e) => DownloadError::ReqwestError(e),
+            other => DownloadError::GenericError(other.to_string()),
+        }
+    }
+}
+
+/// Downloads data with progress tracking
+///
+/// Streams the response body, reporting the percentage (when the server sends
+/// a `Content-Length`) and the number of bytes received through the callback.
+/// Bodies larger than [`DEFAULT_MAX_DOWNLOAD_SIZE`] are rejected; use
+/// [`download_data_with_limit`] for a different maximum.
+///
+/// # Arguments
+///
+/// * `url` - The URL to download from
+/// * `proxy` - Optional SOCKS5 proxy for the client
+/// * `progress_callback` - Callback for reporting download progress
+///
+/// # Returns
+///
+/// A Result containing the downloaded bytes, or a DownloadError.
+pub async fn download_data_with_progress(
+    url: &Url,
+    proxy: Option<SocketAddr>,
+    progress_callback: ProgressCallback,
+) -> Result<Vec<u8>, DownloadError> {
+    download_data_with_limit(url, proxy, DEFAULT_MAX_DOWNLOAD_SIZE, progress_callback).await
+}
+
+/// Downloads data with progress tracking and a size limit
+///
+/// Like [`download_data_with_progress`], but fails with
+/// [`DownloadError::TooLarge`] once the body exceeds `max_size` bytes. A
+/// `Content-Length` over the limit is rejected before any of the body is read.
+///
+/// # Arguments
+///
+/// * `url` - The URL to download from
+/// * `proxy` - Optional SOCKS5 proxy for the client
+/// * `max_size` - The largest body accepted, in bytes
+/// * `progress_callback` - Callback for reporting download progress
+///
+/// # Returns
+///
+/// A Result containing the downloaded bytes, or a DownloadError.
+pub async fn download_data_with_limit(
+    url: &Url,
+    proxy: Option<SocketAddr>,
+    max_size: u64,
+    progress_callback: ProgressCallback,
+) -> Result<Vec<u8>, DownloadError> {
+    let client = make_client(proxy, None)?;
+    let response = client.get(url.clone()).send().await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(DownloadError::HttpStatus(status));
+    }
+
+    let total_size = response.content_length();
+    if total_size.is_some_and(|total| total > max_size) {
+        return Err(DownloadError::TooLarge(max_size));
+    }
+
+    // The header is only a claim, so never reserve more than a bounded buffer
+    let capacity = total_size.unwrap_or_default().min(MAX_PREALLOCATION);
+    let mut data = Vec::with_capacity(capacity as usize);
+    let mut last_percentage = 0;
+
+    // Report initial progress (0%)
+    progress_callback(total_size.map(|_| 0), Some(0)).map_err(DownloadError::GenericError)?;
+
+    let mut stream = response.bytes_stream();
+    loop {
+        let chunk = match tokio::time::timeout(DOWNLOAD_STALL_TIMEOUT, stream.next()).await {
+            Ok(Some(chunk)) => chunk?,
+            Ok(None) => break,
+            Err(_) => return Err(DownloadError::Stalled),
+        };
+        if (data.len() + chunk.len()) as u64 > max_size {
+            return Err(DownloadError::TooLarge(max_size));
+        }
+        data.extend_from_slice(&chunk);
+
+        let received = data.len() as u64;
+        let percentage = match total_size {
+            Some(total) if total > 0 => Some(((received as f64 / total as f64) * 100.0) as u8),
+            _ => None,
+        };
+
+        // Only report when the percentage changes, or on every chunk if it is unknown
+        match percentage {
+            Some(p) if p <= last_percentage => {}
+            _ => {
+                progress_callback(percentage, Some(received))
+                    .map_err(DownloadError::GenericError)?;
+                last_percentage = percentage.unwrap_or(last_percentage);
+            }
+        }
+    }
+
+    debug!("Downloaded {} bytes from {}", data.len(), url);
+
+    // Report 100% completion
+    progress_callback(Some(100), Some(data.len() as u64)).map_err(DownloadError::GenericError)?;
+
+    Ok(data)
+}
@@ -0,0 +1,112 @@
+use log::warn;
+use nostr_sdk::prelude::*;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Relay hints joined for single sends and fetches.
+///
+/// Hints that are not usable through the bot's own pool are added to a
+/// separate pool, so pool-wide publishes and subscriptions on the bot's
+/// client never reach them. Each hint is reference-counted: concurrent
+/// operations on the same URL share one connection, and the relay is only
+/// removed once the last of them has released it. Clones share the pool.
+#[derive(Debug, Clone)]
+pub(crate) struct RelayHints {
+    client: Client,
+    leases: Arc<Mutex<HashMap<RelayUrl, usize>>>,
+}
+
+impl RelayHints {
+    /// Creates an empty hint pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `proxy_addr` - Optional SOCKS5 proxy, as for the bot's own client.
+    /// * `connection_target` - Which relays the proxy is used for.
+    pub(crate) fn new(proxy_addr: Option<SocketAddr>, connection_target: ConnectionTarget) -> Self {
+        let client = match proxy_addr {
+            Some(proxy_addr) => {
+                let connection = Connection::new().proxy(proxy_addr).target(connection_target);
+                Client::builder().opts(Options::new().connection(connection)).build()
+            }
+            None => Client::default(),
+        };
+        Self {
+            client,
+            leases: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The client whose pool holds the joined hints.
+    pub(crate) fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Joins `relays` to the hint pool, or takes another lease on them.
+    ///
+    /// # Arguments
+    ///
+    /// * `relays` - The relay URLs.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the leased URLs, to be passed to
+    /// [`RelayHints::release`], or an error if a URL could not be added (in
+    /// which case no lease is kept).
+    pub(crate) async fn acquire(
+        &self,
+        relays: &[String],
+    ) -> Result<Vec<RelayUrl>, nostr_sdk::client::Error> {
+        let mut acquired = Vec::with_capacity(relays.len());
+        match self.join(relays, &mut acquired).await {
+            Ok(()) => Ok(acquired),
+            Err(e) => {
+                self.release(acquired).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Takes a lease on each of `relays`, adding the ones not leased yet.
+    async fn join(
+        &self,
+        relays: &[String],
+        acquired: &mut Vec<RelayUrl>,
+    ) -> Result<(), nostr_sdk::client::Error> {
+        let mut leases = self.leases.lock().await;
+        for url in relays {
+            let url = RelayUrl::parse(url)
+                .map_err(|e| nostr_sdk::client::Error::RelayPool(e.into()))?;
+            if !leases.contains_key(&url) {
+                self.client.add_relay(url.clone()).await?;
+                if let Err(e) = self.client.connect_relay(url.clone()).await {
+                    warn!("Failed to connect to relay {url}: {e:?}");
+                }
+            }
+            *leases.entry(url.clone()).or_insert(0) += 1;
+            acquired.push(url);
+        }
+        Ok(())
+    }
+
+    /// Gives up leases taken with [`RelayHints::acquire`].
+    ///
+    /// Relays without any remaining lease are removed from the hint pool.
+    pub(crate) async fn release(&self, relays: Vec<RelayUrl>) {
+        let mut leases = self.leases.lock().await;
+        for url in relays {
+            let Some(count) = leases.get_mut(&url) else {
+                continue;
+            };
+            *count -= 1;
+            if *count == 0 {
+                leases.remove(&url);
+                if let Err(e) = self.client.force_remove_relay(url.clone()).await {
+                    warn!("Failed to remove relay {url}: {e:?}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::VectorBotError;
+use log::debug;
+use nostr_sdk::nips::nip49::EncryptedSecretKey;
+use nostr_sdk::prelude::*;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Loads the bot's keys from a file, or generates and saves new ones.
+///
+/// Without a passphrase the key is stored as a plain `nsec`. With one, it is
+/// stored as a NIP-49 `ncryptsec`, so a copy of the file alone does not leak
+/// the identity. New files are created readable only by the owner on Unix.
+/// Loading a plain key with a passphrase fails rather than quietly using an
+/// unencrypted key.
+///
+/// # Arguments
+///
+/// * `path` - The file holding the key.
+/// * `passphrase` - Optional passphrase used to encrypt the stored key.
+///
+/// # Returns
+///
+/// A Result containing the loaded or newly generated keys.
+pub fn load_or_create_keys(
+    path: &Path,
+    passphrase: Option<&str>,
+) -> Result<Keys, VectorBotError> {
+    if path.exists() {
+        return load_keys(path, passphrase);
+    }
+
+    let keys = Keys::generate();
+    let encoded = match passphrase {
+        Some(passphrase) => export_ncryptsec(&keys, passphrase)?,
+        None => keys.secret_key().to_bech32().map_err(|e| storage_error(path, e))?,
+    };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| storage_error(path, e))?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| storage_error(path, e))?;
+    writeln!(file, "{encoded}").map_err(|e| storage_error(path, e))?;
+
+    debug!("Generated new keys at {}", path.display());
+    Ok(keys)
+}
+
+/// Encrypts a secret key into a NIP-49 `ncryptsec` string.
+///
+/// # Arguments
+///
+/// * `keys` - The keys whose secret key should be exported.
+/// * `password` - The password protecting the export.
+///
+/// # Returns
+///
+/// A Result containing the `ncryptsec1...` string.
+pub fn export_ncryptsec(keys: &Keys, password: &str) -> Result<String, VectorBotError> {
+    keys.secret_key()
+        .encrypt(password)
+        .map_err(|e| VectorBotError::Nip49Error(e.to_string()))?
+        .to_bech32()
+        .map_err(|e| VectorBotError::Nip49Error(e.to_string()))
+}
+
+/// Decrypts a NIP-49 `ncryptsec` string into keys.
+///
+/// # Arguments
+///
+/// * `encrypted` - The `ncryptsec1...` string.
+/// * `password` - The password used when exporting.
+///
+/// # Returns
+///
+/// A Result containing the decrypted keys, or an error if the string is
+/// malformed or the password is wrong.
+pub fn import_ncryptsec(encrypted: &str, password: &str) -> Result<Keys, VectorBotError> {
+    let secret_key = EncryptedSecretKey::from_bech32(encrypted.trim())
+        .map_err(|e| VectorBotError::Nip49Error(e.to_string()))?
+        .decrypt(password)
+        .map_err(|e| VectorBotError::Nip49Error(e.to_string()))?;
+    Ok(Keys::new(secret_key))
+}
+
+/// Reads an `nsec`, hex or `ncryptsec` key from a file.
+fn load_keys(path: &Path, passphrase: Option<&str>) -> Result<Keys, VectorBotError> {
+    let contents = fs::read_to_string(path).map_err(|e| storage_error(path, e))?;
+    let contents = contents.trim();
+
+    if contents.starts_with("ncryptsec1") {
+        let passphrase = passphrase.ok_or_else(|| {
+            VectorBotError::KeyStorageError(format!(
+                "{} is encrypted but no passphrase was given",
+                path.display()
+            ))
+        })?;
+        return import_ncryptsec(contents, passphrase);
+    }
+    if passphrase.is_some() {
+        return Err(VectorBotError::KeyStorageError(format!(
+            "{} is not encrypted but a passphrase was given",
+            path.display()
+        )));
+    }
+
+    Keys::parse(contents).map_err(|e| storage_error(path, e))
+}
+
+fn storage_error(path: &Path, e: impl std::fmt::Display) -> VectorBotError {
+    VectorBotError::KeyStorageError(format!("{}: {e}", path.display()))
+}
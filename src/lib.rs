@@ -1,8 +1,12 @@
 use ::url::Url;
-use log::{debug, error};
+use log::{debug, error, warn};
 use nostr_sdk::prelude::*;
 // Re-export the Nostr client type for downstream crates
 pub use nostr_sdk::prelude::Client as NostrClient;
+// Re-export the shutdown token used by VectorBot::run_message_loop
+pub use tokio_util::sync::CancellationToken;
+// NIP-49 encrypted key import/export
+pub use crate::keys::{export_ncryptsec, import_ncryptsec};
 
 // Clean, namespaced re-exports of commonly used Nostr SDK items so downstreams
 // can depend only on vector_sdk.
@@ -12,57 +16,224 @@ pub mod nostr {
         EventBuilder, Tag, TagKind, ToBech32, FromBech32,
     };
     pub use nostr_sdk::RelayPoolNotification;
+    pub use nostr_sdk::client::options::ConnectionTarget;
     pub use nostr_sdk::nips::nip59::UnwrappedGift;
 }
 
+pub mod blossom;
 pub mod client;
+pub mod conversations;
 pub mod crypto;
+pub mod dedup;
+pub mod download;
+mod hints;
+pub mod keys;
+mod media;
+pub mod message;
 pub mod metadata;
+pub mod outbox;
+#[cfg(feature = "mock-server")]
+pub mod mock;
+#[cfg(feature = "test-util")]
+pub mod mock_relay;
+pub mod ratelimit;
+mod reconnect;
+pub mod storage;
 pub mod subscription;
 pub mod upload;
 
-use crate::client::build_client;
+use crate::client::{connect_client_with_metadata_event, create_client, ClientConfig};
+use crate::conversations::ConversationIndex;
+use crate::dedup::{SeenCache, DEFAULT_SEEN_CACHE_CAPACITY};
+use crate::hints::RelayHints;
+use crate::message::{DecodedMessage, ReactionEvent};
+use crate::metadata::MetadataConfig;
+use crate::outbox::Outbox;
+use crate::ratelimit::RateLimiter;
+use crate::reconnect::ReconnectSupervisor;
+use crate::storage::Storage;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::future::Future;
+use futures_util::stream::{self, Stream, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
 use once_cell::sync::OnceCell;
 use sha2::{Digest, Sha256};
 use magical_rs::magical::bytes_read::with_bytes_read;
 use magical_rs::magical::magic::FileKind;
+use thiserror::Error;
 
 static TRUSTED_PRIVATE_NIP96: &str = "https://medea-1-swiss.vectorapp.io";
 static PRIVATE_NIP96_CONFIG: OnceCell<ServerConfig> = OnceCell::new();
 
+/// Default window for randomizing gift wrap timestamps (two days, as in NIP-59).
+pub const DEFAULT_GIFTWRAP_TIME_WINDOW: std::time::Duration =
+    std::time::Duration::from_secs(2 * 24 * 60 * 60);
+
+/// Default limit for relay and HTTP reads such as metadata or NIP-05 lookups.
+pub const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How many gift wraps [`VectorBot::fetch_history`] requests per wanted message.
+///
+/// Gift wraps to the bot from every peer share one filter, so a page has to
+/// over-fetch to find enough messages from a single peer.
+const HISTORY_OVERFETCH: usize = 4;
+
+/// Default limit for the content of an incoming gift wrap (128 KiB).
+pub const DEFAULT_MAX_EVENT_SIZE: usize = 128 * 1024;
+
+/// Maximum number of files [`Channel::send_private_files`] uploads at once.
+pub const MAX_CONCURRENT_FILE_SENDS: usize = 3;
+
+/// Largest file a compressed attachment may inflate to (100 MiB).
+pub const MAX_DECOMPRESSED_SIZE: usize = 100 * 1024 * 1024;
+
+/// Errors that can occur during bot operations
+#[derive(Debug, Error)]
+pub enum VectorBotError {
+    /// Failed to build or sign an event
+    #[error("Event builder error: {0}")]
+    EventBuilderError(#[from] nostr_sdk::event::builder::Error),
+
+    /// Nostr client error
+    #[error("Client error: {0}")]
+    ClientError(#[from] nostr_sdk::client::Error),
+
+    /// Malformed identifier, or the NIP-05 lookup itself failed
+    #[error("NIP-05 error: {0}")]
+    Nip05Error(String),
+
+    /// The NIP-05 document does not list the requested name
+    #[error("NIP-05 name not found: {0}")]
+    Nip05NotFound(String),
+
+    /// Attachment download error
+    #[error("Download error: {0}")]
+    DownloadError(#[from] download::DownloadError),
+
+    /// Encryption or decryption error
+    #[error("Crypto error: {0}")]
+    CryptoError(#[from] crypto::CryptoError),
+
+    /// A metadata URL could not be parsed
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+
+    /// Malformed NIP-49 `ncryptsec`, or the password is wrong
+    #[error("NIP-49 error: {0}")]
+    Nip49Error(String),
+
+    /// Reading, writing or decrypting stored keys failed
+    #[error("Key storage error: {0}")]
+    KeyStorageError(String),
+
+    /// The signer failed or refused a request
+    #[error("Signer error: {0}")]
+    SignerError(#[from] SignerError),
+
+    /// Zap request or LNURL error
+    #[error("Zap error: {0}")]
+    ZapError(String),
+
+    /// A read did not finish within its timeout
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    /// A public key is not a usable secp256k1 point
+    #[error("Invalid public key: {0}")]
+    InvalidPublicKey(String),
+
+    /// Embedded metadata could not be removed from an image
+    #[error("Metadata stripping failed: {0}")]
+    MetadataStripError(String),
+
+    /// Generic error with message
+    #[error("{0}")]
+    GenericError(String),
+}
+
 /// A vector bot that can send and receive private messages.
 ///
 /// This struct represents a vector bot with configured metadata and client.
 /// It provides methods to send private messages and handle notifications.
+/// Cloning is cheap: clones share the same client and bot state, so a bot
+/// can be handed to a task per conversation.
 #[derive(Clone)]
-#[allow(dead_code)]
 pub struct VectorBot {
-    /// The keys used to sign messages.
-    keys: Keys,
+    /// Configuration and caches shared by every clone.
+    inner: Arc<BotState>,
+
+    /// The vector client.
+    pub client: Client,
+}
 
-    /// The name of the user.
-    name: String,
+/// The state behind a [`VectorBot`], shared across clones.
+struct BotState {
+    /// The signer used for events and encryption (local keys or a remote bunker).
+    signer: Arc<dyn NostrSigner>,
 
-    /// The display name of the user.
-    display_name: String,
+    /// The bot's public key, cached from the signer.
+    public_key: PublicKey,
 
-    /// A brief description about the user.
-    about: String,
+    /// The profile metadata published for the bot.
+    metadata: MetadataConfig,
 
-    /// The URL of the user's profile picture.
-    picture: Url,
+    /// The proxy used for relays, reused for upload servers.
+    proxy_addr: Option<SocketAddr>,
 
-    /// The URL of the user's banner.
-    banner: Url,
+    /// Which hosts are routed through `proxy_addr`.
+    connection_target: ConnectionTarget,
 
-    /// The NIP05 identifier.
-    nip05: String,
+    /// NIP-96 server used for attachments instead of the trusted default.
+    upload_server: Option<ServerConfig>,
 
-    /// The LUD16 payment pointer.
-    lud16: String,
+    /// Optional limiter applied before every publish.
+    rate_limiter: Option<RateLimiter>,
 
-    /// The vector client.
-    pub client: Client,
+    /// Event ids already handled.
+    seen_cache: Mutex<SeenCache>,
+
+    /// Last message time per counterparty.
+    conversations: Mutex<ConversationIndex>,
+
+    /// How attachment keys are carried in file rumors.
+    attachment_key_scheme: AttachmentKeyScheme,
+
+    /// Whether image attachments have EXIF/GPS metadata removed before upload.
+    strip_image_metadata: bool,
+
+    /// Whether attachment ciphertext is bound to its hash and MIME type tags.
+    bind_attachment_metadata: bool,
+
+    /// Optional queue retrying sends no relay accepted.
+    outbox: Option<Outbox>,
+
+    /// Relays outside the pool, joined for single sends and fetches.
+    relay_hints: RelayHints,
+
+    /// How far back gift wrap timestamps are randomized.
+    giftwrap_time_window: std::time::Duration,
+
+    /// How long reads from relays and HTTP endpoints may take.
+    read_timeout: std::time::Duration,
+
+    /// Largest attachment download accepted, in bytes.
+    max_download_size: u64,
+
+    /// Optional task reconnecting after every relay drops.
+    reconnect: Option<ReconnectSupervisor>,
+
+    /// Application name for the NIP-89 `client` tag.
+    client_tag: Option<String>,
+
+    /// Whether private messages carry the `client` tag too.
+    client_tag_in_dms: bool,
+
+    /// Largest incoming gift wrap content that will be unwrapped, in bytes.
+    max_event_size: AtomicUsize,
 }
 
 impl VectorBot {
@@ -70,6 +241,7 @@ impl VectorBot {
     ///
     /// This function generates a new VectorBot with default metadata values.
     /// It's a convenient way to quickly create a bot without specifying all metadata.
+    /// Unlike the other constructors, it also connects to relays.
     ///
     /// # Arguments
     ///
@@ -77,9 +249,9 @@ impl VectorBot {
     ///
     /// # Returns
     ///
-    /// A new VectorBot instance with default metadata.
+    /// A new, connected VectorBot instance with default metadata.
     pub async fn quick(keys: Keys) -> Self {
-        Self::new_with_urls(
+        let bot = Self::new_with_urls(
             keys,
             "vector bot".to_string(),
             "Vector Bot".to_string(),
@@ -90,12 +262,62 @@ impl VectorBot {
             "example@example.com".to_string(),
         )
         .await
+        .expect("placeholder metadata URLs are valid");
+        bot.connect_or_warn().await;
+        bot
+    }
+
+    /// Creates a new VectorBot with the given metadata and default settings.
+    ///
+    /// Like [`VectorBot::quick`], but publishes real profile data instead of
+    /// placeholder values. Also connects to relays.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The keys used to sign messages.
+    /// * `metadata` - The profile metadata to publish.
+    ///
+    /// # Returns
+    ///
+    /// A new, connected VectorBot instance with the given metadata.
+    pub async fn quick_with(keys: Keys, metadata: MetadataConfig) -> Self {
+        let bot = VectorBotBuilder::new(keys).metadata(metadata).build().await;
+        bot.connect_or_warn().await;
+        bot
+    }
+
+    /// Creates a new VectorBot that signs through any [`NostrSigner`].
+    ///
+    /// Lets security-conscious deployments keep the secret key in a separate
+    /// NIP-46 bunker process instead of handing `Keys` to the bot. Use
+    /// [`VectorBotBuilder::with_signer`] for the other builder options. Call
+    /// [`VectorBot::connect`] to go online.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - The signer used for events and encryption.
+    /// * `metadata` - The profile metadata to publish.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the VectorBot, or an error if the signer could not
+    /// provide its public key.
+    pub async fn with_signer(
+        signer: Arc<dyn NostrSigner>,
+        metadata: MetadataConfig,
+    ) -> Result<Self, VectorBotError> {
+        let public_key = signer.get_public_key().await?;
+        Ok(VectorBotBuilder::with_signer(signer, public_key)
+            .metadata(metadata)
+            .build()
+            .await)
     }
 
     /// Creates a new VectorBot with custom metadata.
     ///
     /// This function generates a new VectorBot with the provided metadata values.
-    /// It allows for customization of all metadata fields.
+    /// It allows for customization of all metadata fields. No network I/O
+    /// happens until [`VectorBot::connect`] is called.
     ///
     /// # Arguments
     ///
@@ -110,7 +332,9 @@ impl VectorBot {
     ///
     /// # Returns
     ///
-    /// A new VectorBot instance with the specified metadata.
+    /// A Result containing the new VectorBot, or [`VectorBotError::InvalidUrl`]
+    /// if the picture or banner URL cannot be parsed.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         keys: Keys,
         name: impl Into<String>,
@@ -120,7 +344,7 @@ impl VectorBot {
         banner: impl AsRef<str>,
         nip05: impl Into<String>,
         lud16: impl Into<String>,
-    ) -> Self {
+    ) -> Result<Self, VectorBotError> {
         // Convert Into<String> to String so we can pass owned values to the helper.
         let name = name.into();
         let display_name = display_name.into();
@@ -141,9 +365,70 @@ impl VectorBot {
         .await
     }
 
+    /// Creates a new VectorBot with custom metadata, tolerating invalid URLs.
+    ///
+    /// Behaves like [`VectorBot::new`], except an invalid picture or banner URL
+    /// is logged and replaced with a placeholder, and the bot is returned
+    /// without any relays configured.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The keys used to sign messages.
+    /// * `name` - The name of the user.
+    /// * `display_name` - The display name of the user.
+    /// * `about` - A brief description about the user.
+    /// * `picture` - The URL of the user's profile picture.
+    /// * `banner` - The URL of the user's banner.
+    /// * `nip05` - The NIP05 identifier.
+    /// * `lud16` - The LUD16 payment pointer.
+    ///
+    /// # Returns
+    ///
+    /// A new VectorBot instance.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new_lenient(
+        keys: Keys,
+        name: impl Into<String>,
+        display_name: impl Into<String>,
+        about: impl Into<String>,
+        picture: impl AsRef<str>,
+        banner: impl AsRef<str>,
+        nip05: impl Into<String>,
+        lud16: impl Into<String>,
+    ) -> Self {
+        let default_url = Url::parse("https://example.com/default.png").unwrap();
+        let mut metadata = MetadataConfig {
+            name: name.into(),
+            display_name: display_name.into(),
+            about: about.into(),
+            picture: None,
+            banner: None,
+            nip05: Some(nip05.into()),
+            lud16: Some(lud16.into()),
+        };
+
+        let mut valid = true;
+        metadata.picture = Some(Url::parse(picture.as_ref()).unwrap_or_else(|e| {
+            error!("Invalid picture URL: {}", e);
+            valid = false;
+            default_url.clone()
+        }));
+        metadata.banner = Some(Url::parse(banner.as_ref()).unwrap_or_else(|e| {
+            error!("Invalid banner URL: {}", e);
+            valid = false;
+            default_url
+        }));
+
+        if !valid {
+            return Self::unconnected(keys, metadata);
+        }
+        VectorBotBuilder::new(keys).metadata(metadata).build().await
+    }
+
     /// Creates a new VectorBot with the given metadata.
     ///
     /// This is a helper function that handles URL parsing and client building.
+    #[allow(clippy::too_many_arguments)]
     async fn new_with_urls(
         keys: Keys,
         name: String,
@@ -153,226 +438,2730 @@ impl VectorBot {
         banner: impl AsRef<str>,
         nip05: String,
         lud16: String,
-    ) -> Self {
-        let picture_url = match Url::parse(picture.as_ref()) {
-            Ok(url) => url,
-            Err(e) => {
-                error!("Invalid picture URL: {}", e);
-                return Self {
-                    keys: keys.clone(),
-                    name,
-                    display_name,
-                    about,
-                    picture: Url::parse("https://example.com/default.png").unwrap(),
-                    banner: Url::parse("https://example.com/default.png").unwrap(),
-                    nip05,
-                    lud16,
-                    client: Client::builder().signer(keys.clone()).build(),
-                };
-            }
-        };
+    ) -> Result<Self, VectorBotError> {
+        let picture_url = Url::parse(picture.as_ref())
+            .map_err(|e| VectorBotError::InvalidUrl(format!("picture: {e}")))?;
+        let banner_url = Url::parse(banner.as_ref())
+            .map_err(|e| VectorBotError::InvalidUrl(format!("banner: {e}")))?;
 
-        let banner_url = match Url::parse(banner.as_ref()) {
-            Ok(url) => url,
-            Err(e) => {
-                error!("Invalid banner URL: {}", e);
-                return Self {
-                    keys: keys.clone(),
-                    name,
-                    display_name,
-                    about,
-                    picture: picture_url,
-                    banner: Url::parse("https://example.com/default.png").unwrap(),
-                    nip05,
-                    lud16,
-                    client: Client::builder().signer(keys.clone()).build(),
-                };
-            }
+        let metadata = MetadataConfig {
+            name,
+            display_name,
+            about,
+            picture: Some(picture_url),
+            banner: Some(banner_url),
+            nip05: Some(nip05),
+            lud16: Some(lud16),
         };
 
-        let client = build_client(
-            keys.clone(),
-            name.clone(),
-            display_name.clone(),
-            about.clone(),
-            picture_url.clone(),
-            banner_url.clone(),
-            nip05.clone(),
-            lud16.clone(),
-            None,
-        )
-        .await;
+        Ok(VectorBotBuilder::new(keys).metadata(metadata).build().await)
+    }
 
+    /// Creates a new VectorBot with a client that has no relays or metadata set.
+    fn unconnected(keys: Keys, metadata: MetadataConfig) -> Self {
         Self {
-            keys,
-            name,
-            display_name,
-            about,
-            picture: picture_url,
-            banner: banner_url,
-            nip05,
-            lud16,
-            client,
+            client: Client::builder().signer(keys.clone()).build(),
+            inner: Arc::new(BotState {
+                public_key: keys.public_key(),
+                signer: Arc::new(keys),
+                metadata,
+                proxy_addr: None,
+                connection_target: ConnectionTarget::Onion,
+                upload_server: None,
+                rate_limiter: None,
+                seen_cache: Mutex::new(SeenCache::new(DEFAULT_SEEN_CACHE_CAPACITY)),
+                conversations: Mutex::new(ConversationIndex::new()),
+                attachment_key_scheme: AttachmentKeyScheme::default(),
+                strip_image_metadata: true,
+                bind_attachment_metadata: false,
+                outbox: None,
+                relay_hints: RelayHints::new(None, ConnectionTarget::Onion),
+                giftwrap_time_window: DEFAULT_GIFTWRAP_TIME_WINDOW,
+                read_timeout: DEFAULT_READ_TIMEOUT,
+                max_download_size: download::DEFAULT_MAX_DOWNLOAD_SIZE,
+                reconnect: None,
+                client_tag: None,
+                client_tag_in_dms: false,
+                max_event_size: AtomicUsize::new(DEFAULT_MAX_EVENT_SIZE),
+            }),
         }
     }
 
-    /// Gets a chat channel for a specific public key.
+    /// Connects to relays, publishes the bot's metadata and subscribes to gift wraps.
     ///
-    /// This function creates a new Channel instance for communicating with
-    /// a specific recipient.
+    /// Construction never touches the network, so relays can be inspected or
+    /// changed through [`VectorBot::client`] before calling this.
+    ///
+    /// # Returns
+    ///
+    /// A Result that is an error if the metadata could not be published or
+    /// the gift wrap subscription could not be created.
+    pub async fn connect(&self) -> Result<(), VectorBotError> {
+        let metadata = self.with_client_tag(EventBuilder::metadata(&self.inner.metadata.build()));
+        let connected =
+            connect_client_with_metadata_event(&self.client, self.inner.public_key, metadata)
+                .await;
+        if let Some(ref reconnect) = self.inner.reconnect {
+            reconnect.start(self.client.clone(), self.inner.public_key);
+        }
+        connected?;
+        Ok(())
+    }
+
+    /// Connects, logging instead of returning failures.
+    async fn connect_or_warn(&self) {
+        if let Err(e) = self.connect().await {
+            warn!("Failed to connect bot: {e}");
+        }
+    }
+
+    /// Creates a VectorBot connected only to a fresh in-process relay.
+    ///
+    /// Everything the bot publishes is recorded by the returned
+    /// [`mock_relay::MockRelay`], so tests can check the gift wraps a send
+    /// actually produced instead of only its return value.
     ///
     /// # Arguments
     ///
-    /// * `chat_npub` - The public key of the recipient.
+    /// * `keys` - The bot's keys.
     ///
     /// # Returns
     ///
-    /// A Channel instance for communicating with the specified recipient.
-    pub async fn get_chat(&self, chat_npub: PublicKey) -> Channel {
-        Channel::new(chat_npub, self).await
+    /// A Result containing the connected bot and the relay backing it.
+    #[cfg(feature = "test-util")]
+    pub async fn with_mock_client(
+        keys: Keys,
+    ) -> Result<(Self, mock_relay::MockRelay), VectorBotError> {
+        let relay = mock_relay::MockRelay::start()
+            .await
+            .map_err(|e| VectorBotError::GenericError(format!("Mock relay failed: {e}")))?;
+        let config = ClientConfig::builder().relay(relay.url()).build();
+        let bot = VectorBotBuilder::new(keys).client_config(config).build().await;
+        bot.client.connect().await;
+        bot.client.wait_for_connection(std::time::Duration::from_secs(5)).await;
+        bot.connect().await?;
+        Ok((bot, relay))
     }
-}
-
-/// Represents a communication channel with a specific recipient.
-pub struct Channel {
-    recipient: PublicKey,
-    base_bot: VectorBot,
-}
 
-impl Channel {
-    /// Creates a new Channel for communicating with a specific recipient.
+    /// Loads the bot's keys from disk, generating and saving them on first run.
+    ///
+    /// The key is stored as a plain `nsec`; use
+    /// [`VectorBot::load_or_create_keys_with_passphrase`] to encrypt it.
     ///
     /// # Arguments
     ///
-    /// * `chat_npub` - The public key of the recipient.
-    /// * `bot` - A reference to the VectorBot instance.
+    /// * `path` - The file holding the key.
     ///
     /// # Returns
     ///
-    /// A new Channel instance.
-    pub async fn new(chat_npub: PublicKey, bot: &VectorBot) -> Self {
-        Self {
-            recipient: chat_npub,
-            base_bot: bot.clone(),
-        }
+    /// A Result containing the bot's keys.
+    pub fn load_or_create_keys(path: &std::path::Path) -> Result<Keys, VectorBotError> {
+        keys::load_or_create_keys(path, None)
     }
 
-    /// Sends a private message to the recipient.
+    /// Loads the bot's passphrase-encrypted keys, generating and saving them on first run.
+    ///
+    /// The key is stored as a NIP-49 `ncryptsec`. An existing plain `nsec`
+    /// file is refused instead of being loaded unencrypted; convert it with
+    /// [`export_ncryptsec`] first.
     ///
     /// # Arguments
     ///
-    /// * `message` - The message content to send.
+    /// * `path` - The file holding the encrypted key.
+    /// * `passphrase` - The passphrase protecting the key.
     ///
     /// # Returns
     ///
-    /// `true` if the message was sent successfully, `false` otherwise.
-    pub async fn send_private_message(&self, message: &str) -> bool {
-        debug!("Sending private message to: {:?}", self.recipient);
+    /// A Result containing the bot's keys.
+    pub fn load_or_create_keys_with_passphrase(
+        path: &std::path::Path,
+        passphrase: &str,
+    ) -> Result<Keys, VectorBotError> {
+        keys::load_or_create_keys(path, Some(passphrase))
+    }
 
-        // Add millisecond precision tag so clients can order messages sent within the same second
-        let final_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap();
-        let milliseconds = final_time.as_millis() % 1000;
+    /// Creates a new VectorBotBuilder.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The keys used to sign messages.
+    ///
+    /// # Returns
+    ///
+    /// A VectorBotBuilder for configuring the bot.
+    pub fn builder(keys: Keys) -> VectorBotBuilder {
+        VectorBotBuilder::new(keys)
+    }
 
-        match self
-            .base_bot
-            .client
-            .send_private_msg(
-                self.recipient,
-                message,
-                [Tag::custom(TagKind::custom("ms"), [milliseconds.to_string()])],
-            )
-            .await
-        {
-            Ok(_) => true,
-            Err(e) => {
-                error!("Failed to send private message: {:?}", e);
-                false
-            }
-        }
+    /// Checks whether an incoming event has already been handled.
+    ///
+    /// The event id is recorded as seen, so the first call for a given id
+    /// returns `false` and every later call returns `true`. Use this in the
+    /// notification loop to skip gift wraps re-delivered after a reconnect.
+    ///
+    /// # Arguments
+    ///
+    /// * `event_id` - The id of the incoming event.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the event was seen before.
+    pub fn is_duplicate(&self, event_id: EventId) -> bool {
+        self.inner.seen_cache.lock().unwrap().check_and_insert(event_id)
     }
 
+    /// Runs the incoming message loop until the shutdown token is cancelled.
+    ///
+    /// Each new gift wrap is de-duplicated with [`VectorBot::is_duplicate`],
+    /// unwrapped, and passed to `handler`. When `shutdown` fires (or the relay
+    /// pool shuts down), the loop exits and the client disconnects from its
+    /// relays. Seen event ids are persisted as they arrive, so nothing needs
+    /// flushing afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `shutdown` - Token that stops the loop when cancelled.
+    /// * `handler` - Called with every newly received, unwrapped gift.
+    pub async fn run_message_loop<F, Fut>(&self, shutdown: CancellationToken, handler: F)
+    where
+        F: Fn(UnwrappedGift) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut notifications = self.client.notifications();
 
-    pub async fn send_reaction(&self, reference_id: String, emoji: String) -> bool {
-        debug!("Sending a reaction event to: {:?}", self.recipient);
+        loop {
+            let notification = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                notification = notifications.recv() => notification,
+            };
 
-        // We need the reference_event and the emoji, we can create the rest here
+            let event = match notification {
+                Ok(RelayPoolNotification::Event { event, .. }) => event,
+                Ok(RelayPoolNotification::Shutdown) => break,
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Message loop lagged, skipped {skipped} notifications");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
 
-        // Create and send the kind30078 with our typing tag
-        if let Err(err) = send_nip25(
-            &self.base_bot,
-            &self.recipient,
-            reference_id,
-            Kind::PrivateDirectMessage,
-            emoji,
-        )
-        .await
-        {
-            error!("Failed to send attachment rumor: {}", err);
-            return false;
+            if !is_acceptable_gift_wrap(&event, self.max_event_size())
+                || self.is_duplicate(event.id)
+            {
+                continue;
+            }
+
+            match self.client.unwrap_gift_wrap(&event).await {
+                Ok(gift) => {
+                    self.record_conversation(gift.sender, gift.rumor.kind, gift.rumor.created_at);
+                    self.record_unread(&gift);
+                    handler(gift).await
+                }
+                Err(e) => error!("Failed to unwrap gift wrap {}: {:?}", event.id, e),
+            }
         }
-        true
 
+        debug!("Message loop stopped, disconnecting from relays");
+        if let Some(ref reconnect) = self.inner.reconnect {
+            reconnect.stop();
+        }
+        self.client.disconnect().await;
     }
 
-    // Sends a typing indicator
-    pub async fn send_typing_indicator(&self)-> bool {
-        debug!("Sending kind 30078 typing indicator to: {:?}", self.recipient);
+    /// Sets the largest gift wrap the bot will try to unwrap.
+    ///
+    /// Incoming gift wraps whose content is longer than this are dropped
+    /// before any decryption, so an oversized event from a malicious relay
+    /// or sender cannot tie up the message loop. Applies to clones of the
+    /// bot as well. Defaults to [`DEFAULT_MAX_EVENT_SIZE`].
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bytes` - The content length limit in bytes.
+    pub fn set_max_event_size(&self, max_bytes: usize) {
+        self.inner.max_event_size.store(max_bytes, Ordering::Relaxed);
+    }
 
-        // We need to send "typing" & an expiration
-        let content = String::from("typing");
-        // For expiration lets just set max for now
-        let expiration = Timestamp::from_secs(
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-                + 30,
-        );
+    /// Returns the largest gift wrap content the bot will unwrap, in bytes.
+    pub fn max_event_size(&self) -> usize {
+        self.inner.max_event_size.load(Ordering::Relaxed)
+    }
 
-        // Create and send the kind30078 with our typing tag
-        if let Err(err) = send_kind30078(
-            &self.base_bot,
-            &self.recipient,
-            content,
-            expiration,
-        )
-        .await
-        {
-            error!("Failed to send attachment rumor: {}", err);
-            return false;
-        }
-        true
+    /// Lists the counterparties the bot has exchanged messages with.
+    ///
+    /// Messages sent through a [`Channel`] and messages received by
+    /// [`VectorBot::run_message_loop`] are tracked. With a [`Storage`] backend
+    /// configured, the list persists across restarts.
+    ///
+    /// # Returns
+    ///
+    /// Each counterparty with the time of its latest message, newest first.
+    pub fn list_conversations(&self) -> Vec<(PublicKey, Timestamp)> {
+        self.inner.conversations.lock().unwrap().list()
     }
 
+    /// Decodes an unwrapped gift wrap into a text or file message.
+    ///
+    /// # Arguments
+    ///
+    /// * `gift` - The gift unwrapped from an incoming event.
+    ///
+    /// # Returns
+    ///
+    /// The decoded message, carrying the sender and rumor id.
+    pub async fn decode_message(&self, gift: UnwrappedGift) -> DecodedMessage {
+        DecodedMessage::decode_with_signer(&self.inner.signer, gift).await
+    }
 
-    /// Sends a private file to the recipient.
+    /// Fetches a page of past messages received from a peer.
     ///
-    /// This function handles file encryption, uploads the file to a server,
-    /// and sends a notification to the recipient with the file information.
+    /// Gift wraps addressed to the bot are fetched from relays, unwrapped,
+    /// and kept if they came from `peer`. Only incoming messages are found:
+    /// the bot does not wrap a copy of what it sends to itself, so its own
+    /// side of the conversation is not on relays in readable form.
+    ///
+    /// Relays can only page by the gift wrap's timestamp, which is randomized
+    /// and says nothing about when the message was written. Paging therefore
+    /// walks wrap timestamps: wraps are fetched in batches, older and older,
+    /// until at least `limit` messages from `peer` were found or the relays
+    /// run out. A batch is always returned whole, so a page can hold more
+    /// than `limit` messages. Pass the returned cursor as `until` to fetch
+    /// the next page; `None` means the relays have nothing older.
     ///
     /// # Arguments
     ///
-    /// * `file` - The file to send, wrapped in an Option.
+    /// * `peer` - The counterparty of the conversation.
+    /// * `until` - Only gift wraps created at or before this time are read;
+    ///   use [`Timestamp::now`] for the first page.
+    /// * `limit` - How many messages to gather before the page ends.
     ///
     /// # Returns
     ///
-    /// `true` if the file was sent successfully, `false` otherwise.
-    pub async fn send_private_file(&self, file: Option<AttachmentFile>) -> bool {
-        let attached_file = match file {
-            Some(f) => f,
-            None => {
-                error!("No file provided for sending");
-                return false;
+    /// A Result containing the decoded messages, newest first, and the cursor
+    /// for the next page.
+    pub async fn fetch_history(
+        &self,
+        peer: PublicKey,
+        until: Timestamp,
+        limit: usize,
+    ) -> Result<(Vec<DecodedMessage>, Option<Timestamp>), VectorBotError> {
+        if limit == 0 {
+            return Ok((Vec::new(), Some(until)));
+        }
+        let base = subscription::create_gift_wrap_subscription(self.inner.public_key, None, None)
+            .map_err(|e| VectorBotError::GenericError(e.to_string()))?;
+        let batch = limit.saturating_mul(HISTORY_OVERFETCH);
+        let max_event_size = self.max_event_size();
+
+        let mut gifts = Vec::new();
+        let mut cursor = Some(until);
+        while let Some(until) = cursor {
+            if gifts.len() >= limit {
+                break;
             }
-        };
+            let events = self
+                .client
+                .fetch_events(base.clone().until(until).limit(batch), self.inner.read_timeout)
+                .await?;
+            let Some(oldest) = events.iter().map(|event| event.created_at).min() else {
+                cursor = None;
+                break;
+            };
+            let mut events: Vec<Event> = events.into_iter().collect();
 
-        // Calculate the file hash first (before encryption)
-        let file_hash = calculate_file_hash(&attached_file.bytes);
+            if events.len() >= batch {
+                // The limit may have cut the oldest second short, so read all of it
+                // before moving the cursor past it
+                let seen: HashSet<EventId> = events.iter().map(|event| event.id).collect();
+                let second = base.clone().remove_limit().since(oldest).until(oldest);
+                let rest = self.client.fetch_events(second, self.inner.read_timeout).await?;
+                events.extend(rest.into_iter().filter(|event| !seen.contains(&event.id)));
+                cursor = oldest.as_u64().checked_sub(1).map(Timestamp::from_secs);
+            } else {
+                cursor = None;
+            }
+
+            for event in events {
+                if !is_acceptable_gift_wrap(&event, max_event_size) {
+                    continue;
+                }
+                match self.client.unwrap_gift_wrap(&event).await {
+                    Ok(gift) if gift.sender == peer => gifts.push(gift),
+                    Ok(_) => {}
+                    Err(e) => debug!("Skipping history gift wrap {}: {e}", event.id),
+                }
+            }
+        }
+
+        gifts.sort_by_key(|gift| std::cmp::Reverse(gift.rumor.created_at));
+
+        let mut messages = Vec::with_capacity(gifts.len());
+        for gift in gifts {
+            messages.push(self.decode_message(gift).await);
+        }
+        Ok((messages, cursor))
+    }
+
+    /// Subscribes to any filter on the bot's relays.
+    ///
+    /// Use this to watch event kinds beyond the built-in gift wrap
+    /// subscription, such as public notes or zap receipts. Matching events
+    /// are read with [`VectorBot::subscription_events`], and the subscription
+    /// is closed with [`VectorBot::unsubscribe`].
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The events to subscribe to.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the id of the new subscription.
+    pub async fn subscribe_filter(&self, filter: Filter) -> Result<SubscriptionId, VectorBotError> {
+        Ok(self.client.subscribe(filter, None).await?.val)
+    }
+
+    /// Streams the events delivered for one subscription.
+    ///
+    /// Only events arriving after this call are yielded, so call it right
+    /// after [`VectorBot::subscribe_filter`] (or before, with a known id) to
+    /// avoid missing the stored events relays send first. Events relayed by
+    /// several relays are yielded once. The stream ends when the relay pool
+    /// shuts down.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The subscription to stream.
+    ///
+    /// # Returns
+    ///
+    /// A stream of matching events as they arrive.
+    pub fn subscription_events(&self, id: SubscriptionId) -> impl Stream<Item = Event> {
+        let notifications = self.client.notifications();
+
+        stream::unfold(
+            (notifications, HashSet::new()),
+            move |(mut notifications, mut seen)| {
+                let id = id.clone();
+                async move {
+                    loop {
+                        let (subscription_id, event) = match notifications.recv().await {
+                            Ok(RelayPoolNotification::Event {
+                                subscription_id,
+                                event,
+                                ..
+                            }) => (subscription_id, event),
+                            Ok(RelayPoolNotification::Shutdown) | Err(RecvError::Closed) => {
+                                return None
+                            }
+                            Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                        };
+                        if subscription_id == id && seen.insert(event.id) {
+                            return Some((*event, (notifications, seen)));
+                        }
+                    }
+                }
+            },
+        )
+    }
+
+    /// Closes a subscription opened with [`VectorBot::subscribe_filter`].
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The subscription to close.
+    pub async fn unsubscribe(&self, id: &SubscriptionId) {
+        self.client.unsubscribe(id).await;
+    }
+
+    /// Downloads and decrypts a received attachment, reporting progress.
+    ///
+    /// The ciphertext is streamed from the file's URL with the same progress
+    /// callback used for uploads, then decrypted with
+    /// [`message::FileMessage::decrypt`], so bound metadata and compression
+    /// are handled the same way.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The decoded file message.
+    /// * `progress_callback` - Called with the percentage and bytes received.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the decrypted file.
+    pub async fn receive_private_file_with_progress(
+        &self,
+        file: &message::FileMessage,
+        progress_callback: upload::ProgressCallback,
+    ) -> Result<AttachmentFile, VectorBotError> {
+        let url = Url::parse(&file.url)
+            .map_err(|e| VectorBotError::InvalidUrl(format!("{}: {e}", file.url)))?;
+        let data = download::download_data_with_limit(
+            &url,
+            self.proxy_for(&url),
+            self.inner.max_download_size,
+            progress_callback,
+        )
+        .await?;
+        let plaintext = file.decrypt(&data)?;
+        Ok(AttachmentFile::from_bytes(plaintext))
+    }
+
+    /// Fetches a user's NIP-17 DM relay list (kind 10050).
+    ///
+    /// # Arguments
+    ///
+    /// * `pubkey` - The public key whose DM relays should be fetched.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the relay URLs, empty if no list was published.
+    pub async fn fetch_dm_relays(&self, pubkey: PublicKey) -> Result<Vec<String>, VectorBotError> {
+        Ok(self.fetch_dm_relay_list(pubkey).await?.unwrap_or_default())
+    }
+
+    /// Checks whether a user can receive NIP-17 DMs.
+    ///
+    /// A published kind 10050 DM relay list signals that the user reads gift
+    /// wraps. Without one, a sent message may never be seen, so a bot can warn
+    /// or fall back.
+    ///
+    /// # Arguments
+    ///
+    /// * `pubkey` - The public key to check.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing whether a DM relay list exists, and the relays it
+    /// lists (for use with [`Channel::with_relay_hints`]).
+    pub async fn supports_nip17(
+        &self,
+        pubkey: PublicKey,
+    ) -> Result<(bool, Vec<String>), VectorBotError> {
+        Ok(match self.fetch_dm_relay_list(pubkey).await? {
+            Some(relays) => (true, relays),
+            None => (false, Vec::new()),
+        })
+    }
+
+    /// Checks that a contact's NIP-05 identifier really belongs to them.
+    ///
+    /// Reads the `nip05` field from the contact's kind 0 metadata and resolves
+    /// it through the domain's `.well-known/nostr.json`. Anyone can put any
+    /// identifier in their metadata, so only a matching lookup proves it.
+    ///
+    /// # Arguments
+    ///
+    /// * `pubkey` - The contact to verify.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing whether the identifier resolves back to `pubkey`,
+    /// and the identifier itself (None if the contact has not set one).
+    pub async fn verify_contact_nip05(
+        &self,
+        pubkey: PublicKey,
+    ) -> Result<(bool, Option<String>), VectorBotError> {
+        let nip05 = self
+            .client
+            .fetch_metadata(pubkey, self.inner.read_timeout)
+            .await?
+            .and_then(|metadata| metadata.nip05)
+            .filter(|nip05| !nip05.trim().is_empty());
+        let Some(nip05) = nip05 else {
+            return Ok((false, None));
+        };
+
+        let resolved =
+            with_timeout(self.inner.read_timeout, "resolving NIP-05", resolve_nip05(&nip05));
+        let verified = match resolved.await {
+            Ok(resolved) => resolved == pubkey,
+            Err(VectorBotError::Nip05NotFound(_)) => false,
+            Err(e) => return Err(e),
+        };
+        Ok((verified, Some(nip05)))
+    }
+
+    /// Fetches a user's kind 10050 event, returning None if none was published.
+    async fn fetch_dm_relay_list(
+        &self,
+        pubkey: PublicKey,
+    ) -> Result<Option<Vec<String>>, VectorBotError> {
+        let filter = subscription::create_dm_relay_list_subscription(&[pubkey]).limit(1);
+        let events = self.client.fetch_events(filter, self.inner.read_timeout).await?;
+
+        let relays = events.first_owned().map(|event| {
+            event
+                .tags
+                .iter()
+                .filter(|tag| tag.kind() == TagKind::Relay)
+                .filter_map(|tag| tag.content().map(str::to_string))
+                .collect()
+        });
+
+        Ok(relays)
+    }
+
+    /// Measures the round-trip time to a relay in the pool.
+    ///
+    /// Sends a small REQ and times how long the relay takes to answer with
+    /// EOSE, which is useful for picking the fastest relay or spotting
+    /// degraded ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of a relay already added to the client.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the round-trip time, or an error if the relay is not
+    /// in the pool, is disconnected, or does not respond within the read
+    /// timeout ([`VectorBotError::Timeout`]).
+    pub async fn ping_relay(&self, url: &str) -> Result<std::time::Duration, VectorBotError> {
+        let timeout = self.inner.read_timeout;
+        let relay = self.client.relay(url).await?;
+        if !relay.is_connected() {
+            return Err(VectorBotError::GenericError(format!(
+                "Relay {url} is not connected"
+            )));
+        }
+
+        let filter = Filter::new()
+            .author(self.inner.public_key)
+            .kind(Kind::Metadata)
+            .limit(1);
+        let start = std::time::Instant::now();
+        relay
+            .fetch_events(filter, timeout, ReqExitPolicy::ExitOnEOSE)
+            .await
+            .map_err(|e| VectorBotError::GenericError(format!("Ping to {url} failed: {e}")))?;
+        let elapsed = start.elapsed();
+
+        // The subscription closes silently when it times out, so check the clock
+        if elapsed >= timeout {
+            return Err(VectorBotError::Timeout(format!(
+                "relay {url} did not respond within {}s",
+                timeout.as_secs()
+            )));
+        }
+
+        Ok(elapsed)
+    }
+
+    /// Signs and publishes an arbitrary event to the write relays.
+    ///
+    /// This is the low-level escape hatch for event kinds the SDK does not
+    /// wrap yet. Nothing is encrypted or gift-wrapped: the event is public
+    /// and signed with the bot's own key. Prefer [`Channel`] methods for
+    /// private messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The event to sign and publish.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the event id and which relays accepted or
+    /// rejected it.
+    pub async fn send_event(
+        &self,
+        builder: EventBuilder,
+    ) -> Result<Output<EventId>, VectorBotError> {
+        Ok(self.client.send_event_builder(self.with_client_tag(builder)).await?)
+    }
+
+    /// Returns the NIP-89 `client` tag, if one is configured.
+    fn client_tag(&self) -> Option<Tag> {
+        let name = self.inner.client_tag.clone()?;
+        Some(Tag::from_standardized(TagStandard::Client {
+            name,
+            address: None,
+        }))
+    }
+
+    /// Adds the `client` tag to a public event, if one is configured.
+    fn with_client_tag(&self, builder: EventBuilder) -> EventBuilder {
+        match self.client_tag() {
+            Some(tag) => builder.tag(tag),
+            None => builder,
+        }
+    }
+
+    /// Publishes the bot's general user status (NIP-38).
+    ///
+    /// Clients show the status next to the profile, e.g. "available" or
+    /// "away". Publishing again replaces it; an empty status clears it.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status text.
+    /// * `expiration` - When clients should stop showing the status, if ever.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the event id and which relays accepted it.
+    pub async fn set_status(
+        &self,
+        status: &str,
+        expiration: Option<Timestamp>,
+    ) -> Result<Output<EventId>, VectorBotError> {
+        let live_status = LiveStatus {
+            status_type: StatusType::General,
+            expiration,
+            reference: None,
+        };
+        self.send_event(EventBuilder::live_status(live_status, status))
+            .await
+    }
+
+    /// Fetches a user's general status (NIP-38).
+    ///
+    /// # Arguments
+    ///
+    /// * `pubkey` - The user whose status should be fetched.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the status text, or None if the user has no
+    /// status, cleared it, or it has expired.
+    pub async fn fetch_status(&self, pubkey: PublicKey) -> Result<Option<String>, VectorBotError> {
+        let filter = Filter::new()
+            .author(pubkey)
+            .kind(Kind::UserStatus)
+            .identifier(StatusType::General.to_string());
+        let events = self.client.fetch_events(filter, self.inner.read_timeout).await?;
+
+        let status = events
+            .first_owned()
+            .filter(|event| !event.is_expired())
+            .map(|event| event.content)
+            .filter(|content| !content.is_empty());
+        Ok(status)
+    }
+
+    /// Requests deletion of an event the bot published (NIP-09).
+    ///
+    /// The event is looked up first so that events signed by someone else are
+    /// refused here rather than silently ignored by relays.
+    ///
+    /// # Arguments
+    ///
+    /// * `event_id` - The ID of the event to delete.
+    ///
+    /// # Returns
+    ///
+    /// A Result that is Ok once at least one relay accepted the kind 5 event,
+    /// or an error if the event was not found, was not authored by the bot, or
+    /// every relay rejected the request.
+    pub async fn delete_event(&self, event_id: EventId) -> Result<(), VectorBotError> {
+        let events = self
+            .client
+            .fetch_events(Filter::new().id(event_id), self.inner.read_timeout)
+            .await?;
+        let event = events.first_owned().ok_or_else(|| {
+            VectorBotError::GenericError(format!("Event {event_id} not found"))
+        })?;
+        if event.pubkey != self.inner.public_key {
+            return Err(VectorBotError::GenericError(format!(
+                "Event {event_id} was not authored by this bot"
+            )));
+        }
+
+        self.throttle().await;
+        let request = EventDeletionRequest::new().id(event_id);
+        let output = self
+            .client
+            .send_event_builder(self.with_client_tag(EventBuilder::delete(request)))
+            .await?;
+
+        if output.success.is_empty() {
+            let reasons: Vec<String> = output
+                .failed
+                .iter()
+                .map(|(url, reason)| format!("{url}: {reason}"))
+                .collect();
+            return Err(VectorBotError::GenericError(format!(
+                "Deletion of {event_id} rejected by all relays: {}",
+                reasons.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Returns how many sends are queued for retry.
+    ///
+    /// Always zero unless the outbox was enabled with
+    /// [`VectorBotBuilder::outbox`]. Useful for showing a "sending…" state.
+    pub fn pending_count(&self) -> usize {
+        self.inner.outbox.as_ref().map_or(0, Outbox::pending_count)
+    }
+
+    /// Updates the conversation list for a chat or file message.
+    ///
+    /// Reactions, typing indicators and other rumors are not counted as
+    /// messages.
+    fn record_conversation(&self, pubkey: PublicKey, kind: Kind, created_at: Timestamp) {
+        let is_message = kind == Kind::PrivateDirectMessage || kind == Kind::from_u16(15);
+        if is_message {
+            self.inner.conversations
+                .lock()
+                .unwrap()
+                .record(pubkey, created_at);
+        }
+    }
+
+    /// Remembers a received message as unread for [`VectorBot::mark_conversation_read`].
+    fn record_unread(&self, gift: &UnwrappedGift) {
+        let rumor = &gift.rumor;
+        let is_message =
+            rumor.kind == Kind::PrivateDirectMessage || rumor.kind == Kind::from_u16(15);
+        if is_message && gift.sender != self.inner.public_key {
+            let event_id = rumor.id.unwrap_or_else(|| rumor.clone().id());
+            self.inner
+                .conversations
+                .lock()
+                .unwrap()
+                .record_unread(gift.sender, event_id);
+        }
+    }
+
+    /// Sends read receipts for every unread message from a peer.
+    ///
+    /// Messages count as unread once [`VectorBot::run_message_loop`] has
+    /// received them, until they are marked read here. All of them are
+    /// acknowledged in a single receipt rumor. With a [`Storage`] backend
+    /// configured, unread messages survive restarts.
+    ///
+    /// # Arguments
+    ///
+    /// * `peer` - The counterparty whose messages were read.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing how many messages were marked read, or an error
+    /// if the receipt could not be sent (the messages then stay unread).
+    pub async fn mark_conversation_read(&self, peer: PublicKey) -> Result<usize, VectorBotError> {
+        let unread = self.inner.conversations.lock().unwrap().unread(&peer);
+        if unread.is_empty() {
+            return Ok(0);
+        }
+
+        let channel = self.get_chat(peer).await;
+        if !channel.send_receipts(&unread, ReceiptKind::Read).await {
+            return Err(VectorBotError::GenericError(format!(
+                "Failed to send read receipts to {peer}"
+            )));
+        }
+        self.inner
+            .conversations
+            .lock()
+            .unwrap()
+            .clear_unread(&peer, &unread);
+        Ok(unread.len())
+    }
+
+    /// Waits for the rate limiter, if one is configured, before publishing.
+    async fn throttle(&self) {
+        if let Some(ref rate_limiter) = self.inner.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+    }
+
+    /// Returns the configuration of the server attachments are uploaded to.
+    async fn upload_server_config(&self) -> Result<ServerConfig, String> {
+        match self.inner.upload_server {
+            Some(ref conf) => Ok(conf.clone()),
+            None => get_server_config().await,
+        }
+    }
+
+    /// Returns the proxy to use for an HTTP request to the given URL.
+    ///
+    /// Mirrors the relay pool's connection target: with
+    /// [`ConnectionTarget::Onion`] only .onion hosts use the proxy.
+    fn proxy_for(&self, url: &Url) -> Option<SocketAddr> {
+        let is_onion = url
+            .host_str()
+            .is_some_and(|host| host.ends_with(".onion"));
+        match self.inner.connection_target {
+            ConnectionTarget::All => self.inner.proxy_addr,
+            ConnectionTarget::Onion if is_onion => self.inner.proxy_addr,
+            ConnectionTarget::Onion => None,
+        }
+    }
+
+    /// Gets a chat channel for a specific public key.
+    ///
+    /// This function creates a new Channel instance for communicating with
+    /// a specific recipient.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_npub` - The public key of the recipient.
+    ///
+    /// # Returns
+    ///
+    /// A Channel instance for communicating with the specified recipient.
+    pub async fn get_chat(&self, chat_npub: PublicKey) -> Channel {
+        Channel::new(chat_npub, self).await
+    }
+
+    /// Gets a chat channel from a textual identifier.
+    ///
+    /// Accepts an `npub`, a hex public key, or a NIP-05 address such as
+    /// `alice@example.com` (resolved with [`resolve_nip05`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `identifier` - The recipient's npub, hex key or NIP-05 address.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a Channel for the resolved recipient.
+    pub async fn get_chat_str(&self, identifier: &str) -> Result<Channel, VectorBotError> {
+        let identifier = identifier.trim();
+        let pubkey = match PublicKey::parse(identifier) {
+            Ok(pubkey) => pubkey,
+            Err(_) if identifier.contains('@') || identifier.contains('.') => {
+                with_timeout(self.inner.read_timeout, "resolving NIP-05", resolve_nip05(identifier))
+                    .await?
+            }
+            Err(e) => {
+                return Err(VectorBotError::GenericError(format!(
+                    "Invalid public key: {e}"
+                )))
+            }
+        };
+        Ok(self.get_chat(pubkey).await)
+    }
+
+    /// Returns the bot's public key as a bech32 `npub`.
+    pub fn npub(&self) -> String {
+        self.inner
+            .public_key
+            .to_bech32()
+            .unwrap_or_else(|never| match never {})
+    }
+
+    /// Returns the bot's public key as a bech32 `nprofile` with relay hints.
+    ///
+    /// Share this instead of the `npub` so others know where to reach the
+    /// bot. Relays that are not valid URLs are skipped, and trailing relays
+    /// are dropped if the profile would be too long to encode.
+    ///
+    /// # Arguments
+    ///
+    /// * `relays` - Relay URLs to include as hints.
+    ///
+    /// # Returns
+    ///
+    /// The `nprofile1...` string.
+    pub fn nprofile(&self, relays: &[String]) -> String {
+        let mut relays: Vec<RelayUrl> = relays
+            .iter()
+            .filter_map(|relay| match RelayUrl::parse(relay) {
+                Ok(url) if url.as_str().len() <= u8::MAX as usize => Some(url),
+                _ => {
+                    warn!("Skipping invalid relay hint {relay}");
+                    None
+                }
+            })
+            .collect();
+
+        loop {
+            let profile = Nip19Profile {
+                public_key: self.inner.public_key,
+                relays: relays.clone(),
+            };
+            match profile.to_bech32() {
+                Ok(nprofile) => return nprofile,
+                Err(e) if !relays.is_empty() => {
+                    debug!("Dropping relay hint from nprofile: {e}");
+                    relays.pop();
+                }
+                Err(e) => unreachable!("nprofile without relays always encodes: {e}"),
+            }
+        }
+    }
+
+    /// Gets a "Note to Self" channel addressed to the bot's own public key.
+    ///
+    /// Messages and files sent here are gift-wrapped to the bot itself, so they
+    /// arrive through the regular gift wrap subscription and can be unwrapped
+    /// with the bot's own keys. Useful for private notes and backups.
+    ///
+    /// # Returns
+    ///
+    /// A Channel instance whose recipient is the bot.
+    pub async fn self_chat(&self) -> Channel {
+        Channel::new(self.inner.public_key, self).await
+    }
+}
+
+/// Builder for VectorBot.
+///
+/// This struct provides a fluent interface for configuring a bot before it
+/// connects to relays.
+#[derive(Debug, Clone)]
+pub struct VectorBotBuilder {
+    signer: Arc<dyn NostrSigner>,
+    public_key: PublicKey,
+    metadata: MetadataConfig,
+    client_config: ClientConfig,
+    rate_limit: Option<RateLimiter>,
+    seen_cache_capacity: usize,
+    seen_cache_path: Option<PathBuf>,
+    storage: Option<Arc<dyn Storage>>,
+    attachment_key_scheme: AttachmentKeyScheme,
+    strip_image_metadata: bool,
+    bind_attachment_metadata: bool,
+    outbox: bool,
+    outbox_path: Option<PathBuf>,
+    giftwrap_time_window: std::time::Duration,
+    read_timeout: std::time::Duration,
+    max_download_size: u64,
+    auto_reconnect: bool,
+    client_tag: Option<String>,
+    client_tag_in_dms: bool,
+    upload_server: Option<ServerConfig>,
+}
+
+impl VectorBotBuilder {
+    /// Creates a new VectorBotBuilder.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The keys used to sign messages.
+    ///
+    /// # Returns
+    ///
+    /// A new VectorBotBuilder with empty metadata and the default client configuration.
+    pub fn new(keys: Keys) -> Self {
+        Self::with_signer(Arc::new(keys.clone()), keys.public_key())
+    }
+
+    /// Creates a new VectorBotBuilder around any signer.
+    ///
+    /// Use this with a NIP-46 remote signer so the secret key stays in a
+    /// separate bunker process. [`VectorBot::with_signer`] looks up the public
+    /// key for you.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - The signer used for events and encryption.
+    /// * `public_key` - The signer's public key.
+    ///
+    /// # Returns
+    ///
+    /// A new VectorBotBuilder with empty metadata and the default client configuration.
+    pub fn with_signer(signer: Arc<dyn NostrSigner>, public_key: PublicKey) -> Self {
+        Self {
+            signer,
+            public_key,
+            metadata: MetadataConfig::default(),
+            client_config: ClientConfig::default(),
+            rate_limit: None,
+            seen_cache_capacity: DEFAULT_SEEN_CACHE_CAPACITY,
+            seen_cache_path: None,
+            storage: None,
+            attachment_key_scheme: AttachmentKeyScheme::default(),
+            strip_image_metadata: true,
+            bind_attachment_metadata: false,
+            outbox: false,
+            outbox_path: None,
+            giftwrap_time_window: DEFAULT_GIFTWRAP_TIME_WINDOW,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            max_download_size: download::DEFAULT_MAX_DOWNLOAD_SIZE,
+            auto_reconnect: false,
+            client_tag: None,
+            client_tag_in_dms: false,
+            upload_server: None,
+        }
+    }
+
+    /// Sets the profile metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `metadata` - The metadata to publish for the bot.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn metadata(mut self, metadata: MetadataConfig) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Sets the client configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The relay and proxy configuration.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn client_config(mut self, config: ClientConfig) -> Self {
+        self.client_config = config;
+        self
+    }
+
+    /// Limits outbound sends to the given rate.
+    ///
+    /// When the limit is reached, sends wait for capacity rather than failing.
+    /// A rate that is zero, negative, NaN or infinite is logged as a warning
+    /// and replaced with [`ratelimit::FALLBACK_PER_SEC`]; use
+    /// [`RateLimiter::new`] to check a rate up front.
+    ///
+    /// # Arguments
+    ///
+    /// * `per_sec` - Maximum sustained messages per second.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn rate_limit(mut self, per_sec: f64) -> Self {
+        let limiter = RateLimiter::new(per_sec).or_else(|e| {
+            warn!("{e}; limiting to {} per second", ratelimit::FALLBACK_PER_SEC);
+            RateLimiter::new(ratelimit::FALLBACK_PER_SEC)
+        });
+        self.rate_limit = limiter.ok();
+        self
+    }
+
+    /// Sets how many handled event ids the duplicate cache remembers.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of event ids to remember.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn seen_cache_capacity(mut self, capacity: usize) -> Self {
+        self.seen_cache_capacity = capacity;
+        self
+    }
+
+    /// Persists the duplicate cache to a file so restarts skip handled events.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file used to store seen event ids.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn seen_cache_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.seen_cache_path = Some(path.into());
+        self
+    }
+
+    /// Sets the storage backend for the bot's persistent caches.
+    ///
+    /// Holds the seen event ids and the conversation list. Takes precedence
+    /// over [`VectorBotBuilder::seen_cache_path`].
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - The backend, e.g. [`storage::MemoryStorage`] or your own.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn storage(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Sets how attachment decryption parameters are carried in file rumors.
+    ///
+    /// The default, [`AttachmentKeyScheme::Tags`], is understood by every
+    /// Vector client. [`AttachmentKeyScheme::Nip44`] encrypts the parameters
+    /// to the recipient instead, and requires receivers that know the scheme.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheme` - The key scheme to use.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn attachment_key_scheme(mut self, scheme: AttachmentKeyScheme) -> Self {
+        self.attachment_key_scheme = scheme;
+        self
+    }
+
+    /// Controls whether image attachments have embedded metadata removed.
+    ///
+    /// Enabled by default so photos do not leak EXIF data such as GPS
+    /// coordinates to the recipient. While enabled, images whose metadata
+    /// cannot be removed (e.g. HEIC, AVIF or damaged files) are not sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `strip` - Whether to strip image metadata before upload.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn strip_image_metadata(mut self, strip: bool) -> Self {
+        self.strip_image_metadata = strip;
+        self
+    }
+
+    /// Controls whether attachment metadata is bound to the ciphertext.
+    ///
+    /// When enabled, the file hash and MIME type are passed to AES-GCM as
+    /// associated data and the rumor carries an `encryption-aad` tag, so a
+    /// tampered `ox` or `file-type` tag fails decryption. Disabled by default
+    /// because receivers that do not know the tag cannot decrypt such files.
+    ///
+    /// # Arguments
+    ///
+    /// * `bind` - Whether to bind attachment metadata.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn bind_attachment_metadata(mut self, bind: bool) -> Self {
+        self.bind_attachment_metadata = bind;
+        self
+    }
+
+    /// Enables an outbound queue that retries sends no relay accepted.
+    ///
+    /// Failed gift wraps are retried in the background with exponential
+    /// backoff until a relay accepts them. See [`VectorBot::pending_count`].
+    /// Sends that are only queued still report failure (`false`) to their
+    /// caller, since no relay has the message yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to queue failed sends for retry.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn outbox(mut self, enabled: bool) -> Self {
+        self.outbox = enabled;
+        self
+    }
+
+    /// Persists the outbound queue to a file so pending sends survive restarts.
+    ///
+    /// Implies [`VectorBotBuilder::outbox`]. The file holds the signed gift
+    /// wraps only, so message content is never written in the clear.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file used to store pending sends.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn outbox_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.outbox = true;
+        self.outbox_path = Some(path.into());
+        self
+    }
+
+    /// Sets how far into the past gift wrap timestamps are randomized.
+    ///
+    /// NIP-59 backdates the seal and gift wrap `created_at` by a random amount
+    /// so relays cannot tell when a message was sent. A wider window gives
+    /// more timing privacy. A narrower one means fewer messages land outside
+    /// the recipient's `since` filter or arrive out of order. Receivers order
+    /// messages by the rumor's own timestamp, which is never randomized.
+    /// Defaults to two days, matching NIP-59 practice.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - Maximum randomization (zero disables it).
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn giftwrap_time_window(mut self, window: std::time::Duration) -> Self {
+        self.giftwrap_time_window = window;
+        self
+    }
+
+    /// Sets how long reads such as NIP-05 lookups and relay fetches may take.
+    ///
+    /// Relay fetches stop waiting at this limit and return whatever the
+    /// responsive relays sent, so one silent relay does not hide the others.
+    /// HTTP lookups such as NIP-05 fail with [`VectorBotError::Timeout`].
+    /// Defaults to [`DEFAULT_READ_TIMEOUT`].
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The limit for each read.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn read_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Sets the largest attachment download the bot will accept.
+    ///
+    /// Downloads announcing a larger `Content-Length`, or streaming past the
+    /// limit, fail with [`download::DownloadError::TooLarge`]. Defaults to
+    /// [`download::DEFAULT_MAX_DOWNLOAD_SIZE`].
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bytes` - The limit for each download, in bytes.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn max_download_size(mut self, max_bytes: u64) -> Self {
+        self.max_download_size = max_bytes;
+        self
+    }
+
+    /// Enables reconnecting when every relay has dropped.
+    ///
+    /// Once [`VectorBot::connect`] has been called, a background task watches
+    /// relay status. If no relay is connected, it retries with exponential
+    /// backoff (2s up to 5 minutes) and re-subscribes to gift wraps on
+    /// success, keeping long-running bots alive through network blips.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to run the reconnection task.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn auto_reconnect(mut self, enabled: bool) -> Self {
+        self.auto_reconnect = enabled;
+        self
+    }
+
+    /// Identifies the application with a NIP-89 `client` tag.
+    ///
+    /// The tag is added to public events the bot publishes: its metadata,
+    /// status, deletions, zap requests and anything sent through
+    /// [`VectorBot::send_event`]. Private messages are left untagged unless
+    /// [`VectorBotBuilder::client_tag_in_dms`] is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The application name, e.g. "Vector".
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn client_tag(mut self, name: impl Into<String>) -> Self {
+        self.client_tag = Some(name.into());
+        self
+    }
+
+    /// Also adds the `client` tag to private messages.
+    ///
+    /// Off by default: the tag sits inside the encrypted rumor, but it still
+    /// tells every recipient which application the bot runs on. Has no
+    /// effect unless [`VectorBotBuilder::client_tag`] is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether rumors carry the `client` tag.
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn client_tag_in_dms(mut self, enabled: bool) -> Self {
+        self.client_tag_in_dms = enabled;
+        self
+    }
+
+    /// Uploads attachments to the given NIP-96 server.
+    ///
+    /// By default files go to Vector's trusted private server, whose
+    /// configuration is fetched on first use.
+    ///
+    /// # Arguments
+    ///
+    /// * `conf` - The server configuration, e.g. from
+    ///   [`nostr_sdk::nips::nip96::get_server_config`].
+    ///
+    /// # Returns
+    ///
+    /// The builder for method chaining.
+    pub fn upload_server(mut self, conf: ServerConfig) -> Self {
+        self.upload_server = Some(conf);
+        self
+    }
+
+    /// Builds the VectorBot without connecting to relays.
+    ///
+    /// Call [`VectorBot::connect`] on the result to go online.
+    ///
+    /// # Returns
+    ///
+    /// A configured VectorBot.
+    pub async fn build(self) -> VectorBot {
+        let seen_cache = match (&self.storage, &self.seen_cache_path) {
+            (Some(storage), _) => {
+                SeenCache::with_storage(self.seen_cache_capacity, storage.clone())
+                    .unwrap_or_else(|e| {
+                        warn!("Failed to load seen cache from storage: {e}");
+                        SeenCache::new(self.seen_cache_capacity)
+                    })
+            }
+            (None, Some(path)) => SeenCache::with_persistence(self.seen_cache_capacity, path)
+                .unwrap_or_else(|e| {
+                    warn!("Failed to load seen cache from {}: {e}", path.display());
+                    SeenCache::new(self.seen_cache_capacity)
+                }),
+            (None, None) => SeenCache::new(self.seen_cache_capacity),
+        };
+        let conversations = match self.storage {
+            Some(ref storage) => {
+                ConversationIndex::with_storage(storage.clone()).unwrap_or_else(|e| {
+                    warn!("Failed to load conversations from storage: {e}");
+                    ConversationIndex::new()
+                })
+            }
+            None => ConversationIndex::new(),
+        };
+
+        let proxy_addr = self.client_config.proxy_addr;
+        let connection_target = self.client_config.connection_target;
+        let client = create_client(self.signer.clone(), Some(self.client_config)).await;
+        let relay_hints = RelayHints::new(proxy_addr, connection_target);
+
+        let rate_limiter = self.rate_limit;
+        let outbox = match self.outbox_path {
+            Some(ref path) => Some(Outbox::with_persistence(path).unwrap_or_else(|e| {
+                warn!("Failed to load outbox from {}: {e}", path.display());
+                Outbox::new()
+            })),
+            None if self.outbox => Some(Outbox::new()),
+            None => None,
+        };
+        if let Some(ref outbox) = outbox {
+            outbox.spawn_worker(client.clone(), relay_hints.clone(), rate_limiter.clone());
+        }
+
+        VectorBot {
+            inner: Arc::new(BotState {
+                signer: self.signer,
+                public_key: self.public_key,
+                metadata: self.metadata,
+                proxy_addr,
+                connection_target,
+                upload_server: self.upload_server,
+                rate_limiter,
+                seen_cache: Mutex::new(seen_cache),
+                conversations: Mutex::new(conversations),
+                attachment_key_scheme: self.attachment_key_scheme,
+                strip_image_metadata: self.strip_image_metadata,
+                bind_attachment_metadata: self.bind_attachment_metadata,
+                outbox,
+                relay_hints,
+                giftwrap_time_window: self.giftwrap_time_window,
+                read_timeout: self.read_timeout,
+                max_download_size: self.max_download_size,
+                reconnect: self.auto_reconnect.then(ReconnectSupervisor::new),
+                client_tag: self.client_tag,
+                client_tag_in_dms: self.client_tag_in_dms,
+                max_event_size: AtomicUsize::new(DEFAULT_MAX_EVENT_SIZE),
+            }),
+            client,
+        }
+    }
+}
+
+/// Extra options for [`Channel::send_private_file_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct FileSendOptions {
+    /// Text sent alongside the file in a `caption` tag.
+    pub caption: Option<String>,
+    /// Marks the file as a voice note of this many seconds with a `voice` tag.
+    pub voice_duration: Option<f32>,
+    /// Adds a NIP-36 `content-warning` tag so clients blur the file until tapped.
+    pub sensitive: bool,
+}
+
+impl FileSendOptions {
+    /// Sets the caption sent with the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `caption` - The caption text.
+    ///
+    /// # Returns
+    ///
+    /// The options for method chaining.
+    pub fn caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    /// Marks the file as a voice note so clients render a player.
+    ///
+    /// Sends with a negative, NaN or infinite duration are refused.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration_secs` - The length of the recording in seconds.
+    ///
+    /// # Returns
+    ///
+    /// The options for method chaining.
+    pub fn voice(mut self, duration_secs: f32) -> Self {
+        self.voice_duration = Some(duration_secs);
+        self
+    }
+
+    /// Marks the file as sensitive, e.g. a spoiler or graphic content.
+    ///
+    /// # Arguments
+    ///
+    /// * `sensitive` - Whether to add a `content-warning` tag.
+    ///
+    /// # Returns
+    ///
+    /// The options for method chaining.
+    pub fn sensitive(mut self, sensitive: bool) -> Self {
+        self.sensitive = sensitive;
+        self
+    }
+}
+
+/// How attachment decryption parameters are carried inside the rumor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AttachmentKeyScheme {
+    /// Plaintext `decryption-key` and `decryption-nonce` tags (compatible with all clients).
+    #[default]
+    Tags,
+    /// A single NIP-44 encrypted `decryption-params` tag, marked with `key-scheme`.
+    Nip44,
+}
+
+impl AttachmentKeyScheme {
+    /// The value emitted in the rumor's `key-scheme` tag for this scheme.
+    pub fn tag_value(&self) -> &'static str {
+        match self {
+            AttachmentKeyScheme::Tags => "tags",
+            AttachmentKeyScheme::Nip44 => "nip44-v1",
+        }
+    }
+}
+
+/// Compression applied to attachment bytes before encryption.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    /// Raw DEFLATE (RFC 1951)
+    Deflate,
+}
+
+impl CompressionAlgorithm {
+    /// The value emitted in the rumor's `compression` tag for this algorithm.
+    pub fn tag_value(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Deflate => "deflate",
+        }
+    }
+
+    /// Parses a `compression` tag value.
+    pub fn from_tag_value(value: &str) -> Option<Self> {
+        match value {
+            "deflate" => Some(CompressionAlgorithm::Deflate),
+            _ => None,
+        }
+    }
+}
+
+/// How far a receipted message has got on the recipient's side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptKind {
+    /// The message reached the recipient's device (single check mark)
+    Delivered,
+    /// The recipient has seen the message (double check mark)
+    Read,
+}
+
+impl ReceiptKind {
+    /// The rumor content used for this kind of receipt.
+    pub fn content(&self) -> &'static str {
+        match self {
+            ReceiptKind::Delivered => "delivered",
+            ReceiptKind::Read => "read",
+        }
+    }
+
+    /// Parses the content of a receipt rumor.
+    pub fn from_content(content: &str) -> Option<Self> {
+        match content {
+            "delivered" => Some(ReceiptKind::Delivered),
+            "read" => Some(ReceiptKind::Read),
+            _ => None,
+        }
+    }
+}
+
+/// The outcome of publishing a gift wrap on one relay.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayStatus {
+    /// The relay stored the gift wrap
+    Accepted,
+    /// The relay refused the gift wrap or could not be reached
+    Rejected(String),
+    /// Delivery failed and the send is queued in the outbox for retry
+    Pending,
+}
+
+/// Cancels a send started with [`Channel::send_private_file_cancellable`].
+#[derive(Debug, Clone)]
+pub struct CancelHandle {
+    token: CancellationToken,
+}
+
+impl CancelHandle {
+    fn new() -> Self {
+        Self {
+            token: CancellationToken::new(),
+        }
+    }
+
+    /// Cancels the send if its attachment has not been announced yet.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Returns true if [`CancelHandle::cancel`] was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+/// Which relays a gift wrap was published to, and how each one answered.
+#[derive(Debug, Clone, Default)]
+pub struct SendResult {
+    /// The id of the published gift wrap, or None if it was never sent.
+    pub gift_wrap_id: Option<EventId>,
+    /// The outcome on every relay the gift wrap was sent to.
+    pub relays: HashMap<RelayUrl, RelayStatus>,
+}
+
+impl SendResult {
+    /// Returns how many relays accepted the gift wrap.
+    pub fn accepted_count(&self) -> usize {
+        self.relays
+            .values()
+            .filter(|status| **status == RelayStatus::Accepted)
+            .count()
+    }
+
+    /// Returns true if at least one relay accepted the gift wrap.
+    pub fn is_accepted(&self) -> bool {
+        self.accepted_count() > 0
+    }
+
+    /// Returns true if no relay accepted the gift wrap yet and it is queued for retry.
+    pub fn is_queued(&self) -> bool {
+        !self.is_accepted() && self.relays.values().any(|s| *s == RelayStatus::Pending)
+    }
+
+    /// Turns a send that no relay accepted (e.g. one only queued) into an error.
+    ///
+    /// # Returns
+    ///
+    /// The result itself if at least one relay accepted the gift wrap.
+    pub fn accepted(self) -> Result<Self, String> {
+        if self.is_accepted() {
+            return Ok(self);
+        }
+        if self.is_queued() {
+            return Err("No relay accepted gift wrap yet, queued for retry".to_string());
+        }
+        Err(format!("No relay accepted gift wrap: {}", self.failure_summary()))
+    }
+
+    /// Reports a send that could not reach any relay and was queued.
+    fn pending(gift_wrap_id: EventId, relays: Vec<RelayUrl>) -> Self {
+        Self {
+            gift_wrap_id: Some(gift_wrap_id),
+            relays: relays.into_iter().map(|url| (url, RelayStatus::Pending)).collect(),
+        }
+    }
+
+    /// Returns the relays that did not accept the gift wrap, for a targeted retry.
+    pub fn failed_relays(&self) -> Vec<String> {
+        self.relays
+            .iter()
+            .filter(|(_, status)| **status != RelayStatus::Accepted)
+            .map(|(url, _)| url.to_string())
+            .collect()
+    }
+
+    /// Lists each rejecting relay with its reason, for logs and errors.
+    fn failure_summary(&self) -> String {
+        let failures: Vec<String> = self
+            .relays
+            .iter()
+            .filter_map(|(url, status)| match status {
+                RelayStatus::Rejected(reason) => Some(format!("{url}: {reason}")),
+                _ => None,
+            })
+            .collect();
+        if failures.is_empty() {
+            "no relays".to_string()
+        } else {
+            failures.join(", ")
+        }
+    }
+
+    /// Marks every relay that did not accept as pending a retry.
+    fn into_pending(mut self) -> Self {
+        for status in self.relays.values_mut() {
+            if *status != RelayStatus::Accepted {
+                *status = RelayStatus::Pending;
+            }
+        }
+        self
+    }
+}
+
+impl From<Output<EventId>> for SendResult {
+    fn from(output: Output<EventId>) -> Self {
+        let accepted = output
+            .success
+            .into_iter()
+            .map(|url| (url, RelayStatus::Accepted));
+        let rejected = output
+            .failed
+            .into_iter()
+            .map(|(url, reason)| (url, RelayStatus::Rejected(reason)));
+        Self {
+            gift_wrap_id: Some(output.val),
+            relays: accepted.chain(rejected).collect(),
+        }
+    }
+}
+
+/// `d` tag marking a kind 30078 rumor as a receipt.
+const RECEIPT_IDENTIFIER: &str = "vector-receipt";
+
+/// Represents a communication channel with a specific recipient.
+pub struct Channel {
+    recipient: PublicKey,
+    base_bot: VectorBot,
+    relay_hints: Vec<String>,
+}
+
+impl Channel {
+    /// Creates a new Channel for communicating with a specific recipient.
+    ///
+    /// The key is not checked here; sends to an invalid key fail. Use
+    /// [`Channel::try_new`] to reject it up front.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_npub` - The public key of the recipient.
+    /// * `bot` - A reference to the VectorBot instance.
+    ///
+    /// # Returns
+    ///
+    /// A new Channel instance.
+    pub async fn new(chat_npub: PublicKey, bot: &VectorBot) -> Self {
+        if let Err(e) = validate_public_key(&chat_npub) {
+            warn!("Creating channel for unusable recipient: {e}");
+        }
+        Self {
+            recipient: chat_npub,
+            base_bot: bot.clone(),
+            relay_hints: Vec::new(),
+        }
+    }
+
+    /// Creates a new Channel, rejecting recipients that cannot receive messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `chat_npub` - The public key of the recipient.
+    /// * `bot` - A reference to the VectorBot instance.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the Channel, or
+    /// [`VectorBotError::InvalidPublicKey`] if the key is all zeros or not a
+    /// point on the curve.
+    pub async fn try_new(chat_npub: PublicKey, bot: &VectorBot) -> Result<Self, VectorBotError> {
+        validate_public_key(&chat_npub)?;
+        Ok(Self::new(chat_npub, bot).await)
+    }
+
+    /// Returns a copy of this channel that publishes only to the given relays.
+    ///
+    /// NIP-17 recommends delivering gift wraps to the recipient's preferred DM
+    /// relays. Hinted relays are added to the pool as write-only relays when
+    /// a message is sent, so they are not used for subscriptions.
+    ///
+    /// # Arguments
+    ///
+    /// * `relays` - The relay URLs to publish this conversation's gift wraps to.
+    ///
+    /// # Returns
+    ///
+    /// A Channel bound to the given relays.
+    pub fn with_relay_hints(&self, relays: Vec<String>) -> Channel {
+        Channel {
+            recipient: self.recipient,
+            base_bot: self.base_bot.clone(),
+            relay_hints: relays,
+        }
+    }
+
+    /// Returns a copy of this channel bound to the recipient's DM relays.
+    ///
+    /// Fetches the recipient's kind-10050 DM relay list and uses it as relay
+    /// hints. If the recipient has not published one, the channel keeps
+    /// publishing to the bot's own relays.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the Channel bound to the recipient's DM relays.
+    pub async fn with_recipient_dm_relays(&self) -> Result<Channel, VectorBotError> {
+        let relays = self.base_bot.fetch_dm_relays(self.recipient).await?;
+        if relays.is_empty() {
+            debug!("No DM relay list found for {:?}", self.recipient);
+        }
+        Ok(self.with_relay_hints(relays))
+    }
+
+    /// Checks that a published gift wrap can be read back from relays.
+    ///
+    /// A relay may acknowledge an event and still not serve it, e.g. a
+    /// write-only relay that drops gift wraps. Each relay is queried for the
+    /// wrapped event id on its own, so a silent drop on any of them is
+    /// reported. Relays outside the bot's pool are only joined for the check.
+    ///
+    /// # Arguments
+    ///
+    /// * `wrapped_event_id` - The ID of the gift wrap (not the rumor).
+    /// * `relays` - The relays to check; every write relay of the bot if
+    ///   empty, i.e. each relay a send without hints went to.
+    /// * `timeout` - How long to wait for each relay.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing `true` if every relay returned the event, `false`
+    /// if any relay did not (or there is no relay to check), or an error if
+    /// a relay could not be added.
+    pub async fn confirm_delivery(
+        &self,
+        wrapped_event_id: EventId,
+        relays: &[String],
+        timeout: std::time::Duration,
+    ) -> Result<bool, VectorBotError> {
+        let client = &self.base_bot.client;
+        let filter = Filter::new().id(wrapped_event_id).limit(1);
+
+        let write_relays: Vec<String>;
+        let relays = if relays.is_empty() {
+            write_relays = client
+                .relays()
+                .await
+                .into_iter()
+                .filter(|(_, relay)| relay.flags().has(RelayServiceFlags::WRITE, FlagCheck::All))
+                .map(|(url, _)| url.to_string())
+                .collect();
+            &write_relays
+        } else {
+            relays
+        };
+        if relays.is_empty() {
+            return Ok(false);
+        }
+
+        let hints = &self.base_bot.inner.relay_hints;
+        let (pooled, hinted) = split_relay_hints(client, relays, RelayServiceFlags::READ).await;
+        let leased = hints.acquire(&hinted).await?;
+        let targets = pooled
+            .iter()
+            .map(|relay| (client, relay.as_str()))
+            .chain(hinted.iter().map(|relay| (hints.client(), relay.as_str())));
+        let checks = targets.map(|(client, relay)| {
+            let filter = filter.clone();
+            async move {
+                match client.fetch_events_from([relay], filter, timeout).await {
+                    Ok(events) => !events.is_empty(),
+                    Err(e) => {
+                        warn!("Failed to query {relay} for {wrapped_event_id}: {e:?}");
+                        false
+                    }
+                }
+            }
+        });
+        let found = futures_util::future::join_all(checks).await;
+        hints.release(leased).await;
+
+        Ok(found.into_iter().all(|found| found))
+    }
+
+    /// Sends a private message to the recipient.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message content to send.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the message was sent successfully, `false` otherwise.
+    pub async fn send_private_message(&self, message: &str) -> bool {
+        self.send_private_message_with_tags(message, Vec::new()).await
+    }
+
+    /// Sends a private message to the given relays only.
+    ///
+    /// The gift wrap is published with a targeted send instead of going to
+    /// the whole pool, overriding any relay hints on this channel for this
+    /// one message. Relays outside the bot's pool are only joined for this
+    /// send.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message content to send.
+    /// * `relays` - The relay URLs to publish to.
+    ///
+    /// # Returns
+    ///
+    /// `true` if at least one of the relays accepted the message, `false` otherwise.
+    pub async fn send_private_message_to_relays(&self, message: &str, relays: &[String]) -> bool {
+        if relays.is_empty() {
+            error!("No relays given for message to {}", self.recipient);
+            return false;
+        }
+        self.with_relay_hints(relays.to_vec())
+            .send_private_message(message)
+            .await
+    }
+
+    /// Sends a private message carrying extra tags.
+    ///
+    /// Useful for NIP-17 `subject` lines, client tags or app-specific
+    /// markers. The `ms` tag is still appended automatically.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message content to send.
+    /// * `tags` - Additional tags to put on the rumor.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the message was sent successfully, `false` otherwise.
+    pub async fn send_private_message_with_tags(&self, message: &str, tags: Vec<Tag>) -> bool {
+        let published = self.publish_private_message(message, tags).await;
+        match published.and_then(SendResult::accepted) {
+            Ok(_) => true,
+            Err(err) => {
+                error!("Failed to send private message to {}: {}", self.recipient, err);
+                false
+            }
+        }
+    }
+
+    /// Sends a private message and reports the outcome on each relay.
+    ///
+    /// Unlike [`Channel::send_private_message`], the per-relay result is kept
+    /// so a UI can show "sent to 3 of 4 relays" and retry the failed ones
+    /// with [`Channel::send_private_message_to_relays`].
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message content to send.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing which relays accepted, rejected or are still
+    /// retrying the gift wrap, or an error if no relay accepted it. With an
+    /// outbox, a send no relay accepted is returned as Ok with every relay
+    /// [`RelayStatus::Pending`] (see [`SendResult::is_queued`]).
+    pub async fn send_private_message_with_result(
+        &self,
+        message: &str,
+    ) -> Result<SendResult, VectorBotError> {
+        self.publish_private_message(message, Vec::new())
+            .await
+            .map_err(VectorBotError::GenericError)
+    }
+
+    /// Builds a kind 14 rumor with extra tags and publishes it.
+    async fn publish_private_message(
+        &self,
+        message: &str,
+        tags: Vec<Tag>,
+    ) -> Result<SendResult, String> {
+        debug!("Sending private message to: {:?}", self.recipient);
+
+        let rumor = EventBuilder::private_msg_rumor(self.recipient, message)
+            .tags(tags)
+            .tag(current_ms_tag())
+            .build(self.base_bot.inner.public_key);
+
+        publish_rumor(&self.base_bot, &self.recipient, rumor, [], &self.relay_hints).await
+    }
+
+
+    /// Sends a private message with a NIP-17 `subject` tag.
+    ///
+    /// Clients can show the subject as the conversation title, like an email
+    /// subject line.
+    ///
+    /// # Arguments
+    ///
+    /// * `subject` - The subject of the conversation.
+    /// * `message` - The message content to send.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the message was sent successfully, `false` otherwise.
+    pub async fn send_with_subject(&self, subject: &str, message: &str) -> bool {
+        let subject_tag = Tag::custom(TagKind::Subject, [subject]);
+        self.send_private_message_with_tags(message, vec![subject_tag])
+            .await
+    }
+
+    /// Shares a location as a private message with a geohash `g` tag.
+    ///
+    /// The content carries the label and coordinates in plain text so clients
+    /// without location support still show something readable.
+    ///
+    /// # Arguments
+    ///
+    /// * `lat` - Latitude in degrees, between -90 and 90.
+    /// * `lon` - Longitude in degrees, between -180 and 180.
+    /// * `label` - Optional name for the place.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the location was sent successfully, `false` otherwise.
+    pub async fn send_location(&self, lat: f64, lon: f64, label: Option<&str>) -> bool {
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            error!("Invalid coordinates for {}: {lat}, {lon}", self.recipient);
+            return false;
+        }
+
+        let content = match label {
+            Some(label) => format!("{label} ({lat:.6}, {lon:.6})"),
+            None => format!("{lat:.6}, {lon:.6}"),
+        };
+        let geohash = encode_geohash(lat, lon, GEOHASH_PRECISION);
+        let geohash_tag = Tag::from_standardized(TagStandard::Geohash(geohash));
+        self.send_private_message_with_tags(&content, vec![geohash_tag])
+            .await
+    }
+
+    /// Sends a private message quoting another event (NIP-18).
+    ///
+    /// The comment is followed by a `nostr:nevent1...` reference and a `q`
+    /// tag, so clients render the quoted event inline. Unlike a reply, the
+    /// quoted event may come from any conversation, which makes this suited
+    /// to forwarding a message with commentary.
+    ///
+    /// # Arguments
+    ///
+    /// * `quoted_event_id` - The ID of the event being quoted.
+    /// * `quoted_author` - The author of the quoted event.
+    /// * `comment` - Text shown above the quote.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the quote was sent successfully, `false` otherwise.
+    pub async fn send_quote(
+        &self,
+        quoted_event_id: EventId,
+        quoted_author: PublicKey,
+        comment: &str,
+    ) -> bool {
+        let reference = match Nip19Event::new(quoted_event_id)
+            .author(quoted_author)
+            .to_nostr_uri()
+        {
+            Ok(uri) => uri,
+            Err(e) => {
+                error!("Failed to encode quoted event {quoted_event_id}: {e}");
+                return false;
+            }
+        };
+        let content = if comment.is_empty() {
+            reference
+        } else {
+            format!("{comment}\n\n{reference}")
+        };
+        let quote_tag = Tag::from_standardized(TagStandard::Quote {
+            event_id: quoted_event_id,
+            relay_url: None,
+            public_key: Some(quoted_author),
+        });
+        self.send_private_message_with_tags(&content, vec![quote_tag])
+            .await
+    }
+
+    /// Builds the gift-wrapped private message without publishing it.
+    ///
+    /// This produces the exact event [`Channel::send_private_message`] would
+    /// broadcast, which is useful for previews and offline testing.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message content to wrap.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the signed gift-wrap event.
+    pub async fn build_private_message(&self, message: &str) -> Result<Event, VectorBotError> {
+        debug!("Building private message for: {:?}", self.recipient);
+
+        let rumor = EventBuilder::private_msg_rumor(self.recipient, message)
+            .tag(current_ms_tag())
+            .build(self.base_bot.inner.public_key);
+
+        let gift_wrap = gift_wrap_with_window(
+            &self.base_bot.inner.signer,
+            &self.recipient,
+            rumor,
+            [],
+            self.base_bot.inner.giftwrap_time_window,
+        )
+        .await?;
+
+        Ok(gift_wrap)
+    }
+
+    pub async fn send_reaction(&self, reference_id: String, emoji: String) -> bool {
+        debug!("Sending a reaction event to: {:?}", self.recipient);
+
+        // We need the reference_event and the emoji, we can create the rest here
+
+        // Create and send the kind30078 with our typing tag
+        if let Err(err) = send_nip25(
+            &self.base_bot,
+            &self.recipient,
+            &self.relay_hints,
+            reference_id,
+            Kind::PrivateDirectMessage,
+            emoji,
+            Vec::new(),
+        )
+        .await
+        {
+            error!("Failed to send reaction to {}: {}", self.recipient, err);
+            return false;
+        }
+        true
+
+    }
+
+    /// Sends a NIP-30 custom emoji reaction.
+    ///
+    /// The reaction content is `:shortcode:` and an `emoji` tag maps the
+    /// shortcode to its image so clients can render it.
+    ///
+    /// # Arguments
+    ///
+    /// * `reference_id` - The hex ID of the message being reacted to.
+    /// * `shortcode` - The emoji name, made of letters, digits and underscores.
+    /// * `image_url` - The URL of the emoji image.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the reaction was sent successfully, `false` otherwise.
+    pub async fn send_custom_reaction(
+        &self,
+        reference_id: String,
+        shortcode: &str,
+        image_url: &str,
+    ) -> bool {
+        debug!("Sending a custom emoji reaction to: {:?}", self.recipient);
+
+        let valid_shortcode = !shortcode.is_empty()
+            && shortcode.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_shortcode {
+            error!("Invalid emoji shortcode: {:?}", shortcode);
+            return false;
+        }
+        if let Err(err) = Url::parse(image_url) {
+            error!("Invalid emoji URL {}: {}", image_url, err);
+            return false;
+        }
+
+        let emoji_tag = Tag::custom(TagKind::Emoji, [shortcode, image_url]);
+        if let Err(err) = send_nip25(
+            &self.base_bot,
+            &self.recipient,
+            &self.relay_hints,
+            reference_id,
+            Kind::PrivateDirectMessage,
+            format!(":{shortcode}:"),
+            vec![emoji_tag],
+        )
+        .await
+        {
+            error!("Failed to send custom reaction to {}: {}", self.recipient, err);
+            return false;
+        }
+        true
+    }
+
+    /// Removes a reaction sent earlier with [`Channel::send_reaction`].
+    ///
+    /// Sends a gift-wrapped kind 5 deletion referencing the reaction itself,
+    /// not the message that was reacted to.
+    ///
+    /// # Arguments
+    ///
+    /// * `reaction_event_id` - The hex ID of the reaction rumor to remove.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the deletion was sent successfully, `false` otherwise.
+    pub async fn remove_reaction(&self, reaction_event_id: &str) -> bool {
+        debug!("Removing reaction {} sent to: {:?}", reaction_event_id, self.recipient);
+
+        let reaction_id = match EventId::from_hex(reaction_event_id) {
+            Ok(id) => id,
+            Err(err) => {
+                error!("Invalid reaction event id: {}", err);
+                return false;
+            }
+        };
+
+        if let Err(err) = send_nip09(
+            &self.base_bot,
+            &self.recipient,
+            &self.relay_hints,
+            reaction_id,
+            Kind::Reaction,
+        )
+        .await
+        {
+            error!("Failed to remove reaction {} sent to {}: {}", reaction_id, self.recipient, err);
+            return false;
+        }
+        true
+    }
+
+    /// Watches for the recipient's reactions to one message.
+    ///
+    /// Incoming gift wraps are unwrapped independently of
+    /// [`VectorBot::run_message_loop`], so both can run at once. Only kind 7
+    /// rumors from the recipient that reference `event_id` are yielded. The
+    /// stream ends when the relay pool shuts down.
+    ///
+    /// # Arguments
+    ///
+    /// * `event_id` - The id of the rumor whose reactions should be watched.
+    ///
+    /// # Returns
+    ///
+    /// A stream of reactions as they arrive.
+    pub fn watch_reactions(&self, event_id: EventId) -> impl Stream<Item = ReactionEvent> {
+        let client = self.base_bot.client.clone();
+        let recipient = self.recipient;
+        let max_event_size = self.base_bot.max_event_size();
+        let notifications = client.notifications();
+
+        stream::unfold(
+            (client, notifications, HashSet::new()),
+            move |(client, mut notifications, mut seen)| async move {
+                loop {
+                    let event = match notifications.recv().await {
+                        Ok(RelayPoolNotification::Event { event, .. }) => event,
+                        Ok(RelayPoolNotification::Shutdown) | Err(RecvError::Closed) => {
+                            return None
+                        }
+                        Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    };
+                    if !is_acceptable_gift_wrap(&event, max_event_size) || !seen.insert(event.id)
+                    {
+                        continue;
+                    }
+
+                    let Ok(gift) = client.unwrap_gift_wrap(&event).await else {
+                        continue;
+                    };
+                    let reaction = ReactionEvent::from_rumor(gift.sender, &gift.rumor)
+                        .filter(|r| r.sender == recipient && r.target == event_id);
+                    if let Some(reaction) = reaction {
+                        return Some((reaction, (client, notifications, seen)));
+                    }
+                }
+            },
+        )
+    }
+
+    // Sends a typing indicator
+    pub async fn send_typing_indicator(&self)-> bool {
+        self.send_typing_indicator_for(std::time::Duration::from_secs(30))
+            .await
+    }
+
+    /// Sends a typing indicator that expires after the given time.
+    ///
+    /// # Arguments
+    ///
+    /// * `ttl` - How long the other side should show the indicator.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the indicator was sent successfully, `false` otherwise.
+    pub async fn send_typing_indicator_for(&self, ttl: std::time::Duration) -> bool {
+        debug!("Sending kind 30078 typing indicator to: {:?}", self.recipient);
+
+        // We need to send "typing" & an expiration
+        let content = String::from("typing");
+        let expiration = Timestamp::from_secs(Timestamp::now().as_u64() + ttl.as_secs());
+
+        // Create and send the kind30078 with our typing tag
+        if let Err(err) = send_kind30078(
+            &self.base_bot,
+            &self.recipient,
+            &self.relay_hints,
+            content,
+            expiration,
+        )
+        .await
+        {
+            error!("Failed to send typing indicator to {}: {}", self.recipient, err);
+            return false;
+        }
+        true
+    }
+
+    /// Tells the recipient that one of their messages was delivered or read.
+    ///
+    /// The receipt is a gift-wrapped kind 30078 rumor referencing the message
+    /// with an `e` tag; the other side reads it with [`parse_receipt`].
+    ///
+    /// # Arguments
+    ///
+    /// * `event_id` - The id of the received rumor.
+    /// * `kind` - Whether the message was delivered or read.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the receipt was sent successfully, `false` otherwise.
+    pub async fn send_receipt(&self, event_id: EventId, kind: ReceiptKind) -> bool {
+        self.send_receipts(&[event_id], kind).await
+    }
+
+    /// Sends one receipt covering several messages.
+    ///
+    /// Each message gets its own `e` tag in a single rumor, so acknowledging
+    /// a whole conversation costs one gift wrap. The other side reads it with
+    /// [`parse_receipts`].
+    ///
+    /// # Arguments
+    ///
+    /// * `event_ids` - The ids of the received rumors.
+    /// * `kind` - Whether the messages were delivered or read.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the receipt was sent successfully, `false` otherwise.
+    pub async fn send_receipts(&self, event_ids: &[EventId], kind: ReceiptKind) -> bool {
+        if event_ids.is_empty() {
+            error!("No messages given for receipt to {}", self.recipient);
+            return false;
+        }
+        debug!(
+            "Sending {} receipt for {} messages to {}",
+            kind.content(),
+            event_ids.len(),
+            self.recipient
+        );
+
+        let rumor = EventBuilder::new(Kind::ApplicationSpecificData, kind.content())
+            .tag(Tag::public_key(self.recipient))
+            .tags(event_ids.iter().copied().map(Tag::event))
+            .tag(Tag::identifier(RECEIPT_IDENTIFIER))
+            .tag(current_ms_tag())
+            .build(self.base_bot.inner.public_key);
+
+        if let Err(err) =
+            publish_rumor(&self.base_bot, &self.recipient, rumor, [], &self.relay_hints)
+                .await
+                .and_then(SendResult::accepted)
+        {
+            error!("Failed to send receipt to {}: {}", self.recipient, err);
+            return false;
+        }
+        true
+    }
+
+    /// Clears the typing indicator on the other side.
+    ///
+    /// Sends an indicator that has already expired, replacing the previous
+    /// one so the recipient stops showing it right away.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the indicator was sent successfully, `false` otherwise.
+    pub async fn send_stopped_typing(&self) -> bool {
+        self.send_typing_indicator_for(std::time::Duration::ZERO)
+            .await
+    }
+
+
+    /// Requests a Lightning invoice to zap the recipient (NIP-57).
+    ///
+    /// Looks up the recipient's `lud16` address, fetches its LNURL-pay
+    /// endpoint, signs a zap request and asks the endpoint for an invoice.
+    /// The caller is responsible for paying the returned invoice.
+    ///
+    /// # Arguments
+    ///
+    /// * `amount_msats` - The zap amount in millisatoshis.
+    /// * `comment` - Optional message attached to the zap.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the bolt11 invoice string to pay.
+    pub async fn send_zap_request(
+        &self,
+        amount_msats: u64,
+        comment: Option<&str>,
+    ) -> Result<String, VectorBotError> {
+        let bot = &self.base_bot;
+
+        // Find the recipient's Lightning address
+        let metadata = bot
+            .client
+            .fetch_metadata(self.recipient, bot.inner.read_timeout)
+            .await?
+            .ok_or_else(|| VectorBotError::ZapError("Recipient has no metadata".into()))?;
+        let lud16 = metadata
+            .lud16
+            .ok_or_else(|| VectorBotError::ZapError("Recipient has no lud16 address".into()))?;
+        let (name, domain) = lud16
+            .split_once('@')
+            .ok_or_else(|| VectorBotError::ZapError(format!("Invalid lud16 address: {lud16}")))?;
+
+        // Fetch the LNURL-pay parameters
+        let http = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(15))
+            .build()
+            .map_err(|e| VectorBotError::ZapError(e.to_string()))?;
+        let lnurlp: serde_json::Value = http
+            .get(format!("https://{domain}/.well-known/lnurlp/{name}"))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| VectorBotError::ZapError(format!("LNURL lookup failed: {e}")))?
+            .json()
+            .await
+            .map_err(|e| VectorBotError::ZapError(format!("Invalid LNURL response: {e}")))?;
+
+        if lnurlp.get("allowsNostr").and_then(|v| v.as_bool()) != Some(true) {
+            return Err(VectorBotError::ZapError(format!("{lud16} does not accept zaps")));
+        }
+        let min = lnurlp.get("minSendable").and_then(|v| v.as_u64()).unwrap_or(0);
+        let max = lnurlp.get("maxSendable").and_then(|v| v.as_u64()).unwrap_or(u64::MAX);
+        if amount_msats < min || amount_msats > max {
+            return Err(VectorBotError::ZapError(format!(
+                "Amount {amount_msats} msats outside allowed range {min}-{max}"
+            )));
+        }
+        let callback = lnurlp
+            .get("callback")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| VectorBotError::ZapError("LNURL response has no callback".into()))?;
+
+        // Build and sign the zap request
+        let relays: Vec<String> = bot
+            .client
+            .relays()
+            .await
+            .keys()
+            .map(|url| url.to_string())
+            .collect();
+        let zap_request = EventBuilder::new(Kind::ZapRequest, comment.unwrap_or_default())
+            .tag(Tag::public_key(self.recipient))
+            .tag(Tag::custom(TagKind::Relays, relays))
+            .tag(Tag::custom(TagKind::Amount, [amount_msats.to_string()]));
+        let zap_request = bot.with_client_tag(zap_request).sign(&bot.inner.signer).await?;
+
+        // Ask the callback for an invoice
+        let mut callback_url = Url::parse(callback)
+            .map_err(|e| VectorBotError::ZapError(format!("Invalid callback URL: {e}")))?;
+        callback_url
+            .query_pairs_mut()
+            .append_pair("amount", &amount_msats.to_string())
+            .append_pair("nostr", &zap_request.as_json());
+        let invoice: serde_json::Value = http
+            .get(callback_url)
+            .send()
+            .await
+            .map_err(|e| VectorBotError::ZapError(format!("Invoice request failed: {e}")))?
+            .json()
+            .await
+            .map_err(|e| VectorBotError::ZapError(format!("Invalid invoice response: {e}")))?;
+
+        match invoice.get("pr").and_then(|v| v.as_str()) {
+            Some(pr) => Ok(pr.to_string()),
+            None => {
+                let reason = invoice
+                    .get("reason")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("no invoice returned");
+                Err(VectorBotError::ZapError(reason.to_string()))
+            }
+        }
+    }
+
+    /// Sends a private file to the recipient.
+    ///
+    /// This function handles file encryption, uploads the file to a server,
+    /// and sends a notification to the recipient with the file information.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file to send, wrapped in an Option.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the file was sent successfully, `false` otherwise.
+    pub async fn send_private_file(&self, file: Option<AttachmentFile>) -> bool {
+        self.send_private_file_with_options(file, FileSendOptions::default()).await
+    }
+
+    /// Sends a private file to the recipient with extra options.
+    ///
+    /// Like [`Channel::send_private_file`], but allows a caption to travel in
+    /// the same rumor as the file so both arrive as one message.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file to send, wrapped in an Option.
+    /// * `options` - Extra options for the attachment rumor.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the file was sent successfully, `false` otherwise.
+    pub async fn send_private_file_with_options(
+        &self,
+        file: Option<AttachmentFile>,
+        options: FileSendOptions,
+    ) -> bool {
+        let attached_file = match file {
+            Some(f) => f,
+            None => {
+                error!("No file provided for sending");
+                return false;
+            }
+        };
+        if let Some(duration) = options.voice_duration {
+            if !duration.is_finite() || duration < 0.0 {
+                error!("Invalid voice duration for {}: {}", self.recipient, duration);
+                return false;
+            }
+        }
+
+        // Get server config
+        let conf = match self.base_bot.upload_server_config().await {
+            Ok(c) => c,
+            Err(err) => {
+                error!("Failed to get server config: {}", err);
+                return false;
+            }
+        };
+
+        self.send_file_with_config(attached_file, &conf, &options, &CancellationToken::new())
+            .await
+    }
+
+    /// Sends a private file that can be cancelled before it is announced.
+    ///
+    /// Nothing happens until the returned future is awaited. Cancelling the
+    /// handle while the file is being prepared or uploaded makes the future
+    /// resolve to `false` without publishing the attachment rumor, so the
+    /// recipient never sees a partial send. Once the rumor is being
+    /// published, cancelling has no effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file to send.
+    ///
+    /// # Returns
+    ///
+    /// The send, resolving to `true` if the file was sent, and a handle that
+    /// cancels it.
+    pub fn send_private_file_cancellable(
+        &self,
+        file: AttachmentFile,
+    ) -> (impl Future<Output = bool> + '_, CancelHandle) {
+        let handle = CancelHandle::new();
+        let cancel = handle.token.clone();
+        let send = async move {
+            let conf = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => {
+                    debug!("File send to {} cancelled", self.recipient);
+                    return false;
+                }
+                conf = self.base_bot.upload_server_config() => match conf {
+                    Ok(c) => c,
+                    Err(err) => {
+                        error!("Failed to get server config: {}", err);
+                        return false;
+                    }
+                },
+            };
+            self.send_file_with_config(file, &conf, &FileSendOptions::default(), &cancel)
+                .await
+        };
+        (send, handle)
+    }
+
+    /// Sends an audio file as a voice note.
+    ///
+    /// The attachment gets a `voice` tag carrying its duration, so recipients
+    /// show an inline player instead of a generic file card.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio` - The recorded audio.
+    /// * `duration_secs` - The length of the recording in seconds.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the voice note was sent successfully, `false` otherwise.
+    pub async fn send_voice_message(&self, audio: AttachmentFile, duration_secs: f32) -> bool {
+        if !mime_type_for_extension(&audio.extension).starts_with("audio/") {
+            error!("Voice message for {} is not audio: {}", self.recipient, audio.extension);
+            return false;
+        }
+
+        let options = FileSendOptions::default().voice(duration_secs);
+        self.send_private_file_with_options(Some(audio), options).await
+    }
+
+    /// Sends several private files to the recipient concurrently.
+    ///
+    /// Up to [`MAX_CONCURRENT_FILE_SENDS`] files are encrypted and uploaded at
+    /// once, and the server configuration is fetched a single time for the
+    /// whole batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - The files to send.
+    ///
+    /// # Returns
+    ///
+    /// One entry per file, in the same order, `true` if that file was sent.
+    pub async fn send_private_files(&self, files: Vec<AttachmentFile>) -> Vec<bool> {
+        let conf = match self.base_bot.upload_server_config().await {
+            Ok(c) => c,
+            Err(err) => {
+                error!("Failed to get server config: {}", err);
+                return vec![false; files.len()];
+            }
+        };
+
+        let options = FileSendOptions::default();
+        let cancel = CancellationToken::new();
+        stream::iter(files)
+            .map(|file| self.send_file_with_config(file, &conf, &options, &cancel))
+            .buffered(MAX_CONCURRENT_FILE_SENDS)
+            .collect()
+            .await
+    }
+
+    /// Re-uploads an attachment under a fresh encryption key.
+    ///
+    /// Downloads the ciphertext at `old_url`, decrypts it with `old_params`,
+    /// encrypts it again with newly generated params and uploads the result.
+    /// Use this when a key may have leaked; the old upload is left in place.
+    /// Files sent with bound metadata cannot be rotated this way.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_url` - Where the encrypted file is currently hosted.
+    /// * `old_params` - The key and nonce the file is encrypted with.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the new URL and encryption params.
+    pub async fn reupload_with_new_key(
+        &self,
+        old_url: &Url,
+        old_params: &crypto::EncryptionParams,
+    ) -> Result<(Url, crypto::EncryptionParams), VectorBotError> {
+        let data = download::download_data_with_limit(
+            old_url,
+            self.base_bot.proxy_for(old_url),
+            self.base_bot.inner.max_download_size,
+            create_progress_callback(self.recipient),
+        )
+        .await?;
+        let plaintext = crypto::decrypt_data(&data, old_params)?;
+
+        let params = crypto::generate_encryption_params()?;
+        let enc_file = crypto::encrypt_data(&plaintext, &params)?;
+
+        let conf = self
+            .base_bot
+            .upload_server_config()
+            .await
+            .map_err(VectorBotError::GenericError)?;
+        let url = upload_file(
+            &self.base_bot.inner.signer,
+            &conf,
+            self.base_bot.proxy_for(&conf.api_url),
+            &enc_file,
+            "application/octet-stream",
+            create_progress_callback(self.recipient),
+        )
+        .await
+        .map_err(VectorBotError::GenericError)?;
+
+        debug!("Re-uploaded {} for {} as {}", old_url, self.recipient, url);
+        Ok((url, params))
+    }
+
+    /// Encrypts, uploads and announces one file using an already fetched server config.
+    async fn send_file_with_config(
+        &self,
+        attached_file: AttachmentFile,
+        conf: &ServerConfig,
+        options: &FileSendOptions,
+        cancel: &CancellationToken,
+    ) -> bool {
 
         // Format a Mime Type from the file extension
-        let mime_type = get_mime_type(&attached_file.extension);
+        let mime_type = mime_type_for_extension(&attached_file.extension);
+
+        // Remove EXIF/GPS metadata from images unless the bot opted out; an
+        // image that cannot be cleaned is not sent at all
+        let strip = self.base_bot.inner.strip_image_metadata && mime_type.starts_with("image/");
+        let attached_file = if strip {
+            match attached_file.strip_metadata() {
+                Ok(file) => file,
+                Err(err) => {
+                    error!("Refusing to send image to {}: {}", self.recipient, err);
+                    return false;
+                }
+            }
+        } else {
+            attached_file
+        };
+
+        // Hash the file as the receiver will see it: before compression and encryption
+        let file_hash = match attached_file.original_hash {
+            Some(ref hash) if attached_file.compression.is_some() => hash.clone(),
+            _ => calculate_file_hash(&attached_file.bytes),
+        };
 
         // Generate encryption parameters and encrypt the file
         let params_result = crypto::generate_encryption_params();
@@ -384,58 +3173,69 @@ impl Channel {
             }
         };
 
-        let enc_file = match crypto::encrypt_data(attached_file.bytes.as_slice(), &params) {
-            Ok(data) => data,
-            Err(err) => {
-                error!("Failed to encrypt file: {}", err);
-                return false;
-            }
+        // Optionally bind the hash and MIME type tags to the ciphertext
+        let aad = if self.base_bot.inner.bind_attachment_metadata {
+            crypto::attachment_aad(&file_hash, &mime_type)
+        } else {
+            Vec::new()
         };
-        let file_size = enc_file.len();
 
-        // Get server config
-        let conf = match get_server_config().await {
-            Ok(c) => c,
+        let enc_file = match crypto::encrypt_data_with_aad(&attached_file.bytes, &params, &aad) {
+            Ok(data) => data,
             Err(err) => {
-                error!("Failed to get server config: {}", err);
+                error!("Failed to encrypt file for {}: {}", self.recipient, err);
                 return false;
             }
         };
+        let file_size = enc_file.len();
 
         // Create a progress callback for file uploads
-        let progress_callback = create_progress_callback();
+        let progress_callback = create_progress_callback(self.recipient);
 
-        // Upload the file
-        let url = match upload_file(
-            &self.base_bot.keys,
-            &conf,
+        // Upload the file, giving up early if the send was cancelled
+        let upload = upload_file(
+            &self.base_bot.inner.signer,
+            conf,
+            self.base_bot.proxy_for(&conf.api_url),
             &enc_file,
             &mime_type,
             progress_callback,
-        )
-        .await
-        {
-            Ok(u) => u,
-            Err(err) => {
-                error!("Failed to upload file: {}", err);
+        );
+        let url = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => {
+                debug!("File send to {} cancelled", self.recipient);
                 return false;
             }
+            uploaded = upload => match uploaded {
+                Ok(u) => u,
+                Err(err) => {
+                    error!("Failed to upload file for {}: {}", self.recipient, err);
+                    return false;
+                }
+            },
         };
+        if cancel.is_cancelled() {
+            debug!("File send to {} cancelled after upload", self.recipient);
+            return false;
+        }
 
         // Create and send the attachment rumor
         if let Err(err) = send_attachment_rumor(
             &self.base_bot,
             &self.recipient,
+            &self.relay_hints,
             &url,
             &attached_file,
             &params,
             &file_hash,
             file_size,
             &mime_type,
+            options,
         )
         .await
         {
-            error!("Failed to send attachment rumor: {}", err);
+            error!("Failed to send attachment rumor to {}: {}", self.recipient, err);
             return false;
         }
 
@@ -443,22 +3243,184 @@ impl Channel {
     }
 }
 
+/// Runs a read, failing with [`VectorBotError::Timeout`] if it outlasts `timeout`.
+///
+/// # Arguments
+///
+/// * `timeout` - How long the read may take.
+/// * `what` - A short description of the read for the error message.
+/// * `read` - The read to run.
+///
+/// # Returns
+///
+/// The read's own result, or a Timeout error.
+async fn with_timeout<T, E, F>(
+    timeout: std::time::Duration,
+    what: &str,
+    read: F,
+) -> Result<T, VectorBotError>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<VectorBotError>,
+{
+    tokio::time::timeout(timeout, read)
+        .await
+        .map_err(|_| VectorBotError::Timeout(format!("{what} after {}s", timeout.as_secs())))?
+        .map_err(Into::into)
+}
+
+/// Resolves a NIP-05 identifier to a public key.
+///
+/// Fetches `https://<domain>/.well-known/nostr.json?name=<name>` and returns
+/// the key mapped to the name. A bare domain or `_@domain` resolves the
+/// domain's root identity.
+///
+/// # Arguments
+///
+/// * `identifier` - The NIP-05 address, e.g. `alice@example.com`.
+///
+/// # Returns
+///
+/// A Result containing the resolved public key. Returns
+/// [`VectorBotError::Nip05NotFound`] if the domain does not list the name.
+pub async fn resolve_nip05(identifier: &str) -> Result<PublicKey, VectorBotError> {
+    let identifier = identifier.trim().to_lowercase();
+    let (name, domain) = match identifier.split_once('@') {
+        Some((name, domain)) => (name.to_string(), domain.to_string()),
+        None => ("_".to_string(), identifier.clone()),
+    };
+    if name.is_empty() || domain.is_empty() || domain.contains('/') {
+        return Err(VectorBotError::Nip05Error(format!(
+            "Invalid identifier: {identifier}"
+        )));
+    }
+
+    let url = Url::parse_with_params(
+        &format!("https://{domain}/.well-known/nostr.json"),
+        [("name", name.as_str())],
+    )
+    .map_err(|e| VectorBotError::Nip05Error(format!("Invalid domain {domain}: {e}")))?;
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| VectorBotError::Nip05Error(e.to_string()))?;
+    let json: serde_json::Value = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| VectorBotError::Nip05Error(format!("Lookup failed for {domain}: {e}")))?
+        .json()
+        .await
+        .map_err(|e| VectorBotError::Nip05Error(format!("Invalid nostr.json from {domain}: {e}")))?;
+
+    let hex = json
+        .get("names")
+        .and_then(|names| names.get(&name))
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| VectorBotError::Nip05NotFound(identifier.clone()))?;
+
+    PublicKey::from_hex(hex)
+        .map_err(|e| VectorBotError::Nip05Error(format!("Invalid key for {identifier}: {e}")))
+}
+
 /// Derives the MIME type from a file extension.
 ///
 /// # Arguments
 ///
-/// * `extension` - The file extension.
+/// * `extension` - The file extension, with or without a leading dot.
 ///
 /// # Returns
 ///
-/// The MIME type as a string.
-fn get_mime_type(extension: &str) -> String {
+/// The MIME type as a string, `application/octet-stream` if unknown.
+pub fn mime_type_for_extension(extension: &str) -> String {
     // Prefer mime_guess to derive a correct MIME from the extension.
     // Fallback to application/octet-stream if unknown.
+    let extension = extension.trim_start_matches('.');
     let mime = mime_guess::from_ext(extension).first_or_octet_stream();
     mime.essence_str().to_string()
 }
 
+/// Preferred extensions for common attachment MIME types.
+///
+/// mime_guess lists extensions in no particular order (e.g. `jpe` for
+/// `image/jpeg`), so the usual ones are pinned here.
+const MIME_EXTENSIONS: &[(&str, &str)] = &[
+    // Images
+    ("image/jpeg", "jpg"),
+    ("image/png", "png"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("image/bmp", "bmp"),
+    ("image/tiff", "tiff"),
+    ("image/heic", "heic"),
+    ("image/heif", "heif"),
+    ("image/avif", "avif"),
+    ("image/svg+xml", "svg"),
+    // Audio
+    ("audio/mpeg", "mp3"),
+    ("audio/mp4", "m4a"),
+    ("audio/x-m4a", "m4a"),
+    ("audio/m4a", "m4a"),
+    ("audio/aac", "aac"),
+    ("audio/ogg", "ogg"),
+    ("audio/opus", "opus"),
+    ("audio/wav", "wav"),
+    ("audio/x-wav", "wav"),
+    ("audio/wave", "wav"),
+    ("audio/flac", "flac"),
+    ("audio/x-flac", "flac"),
+    ("audio/webm", "weba"),
+    // Video
+    ("video/mp4", "mp4"),
+    ("video/quicktime", "mov"),
+    ("video/webm", "webm"),
+    ("video/x-matroska", "mkv"),
+    ("video/x-msvideo", "avi"),
+    ("video/mpeg", "mpeg"),
+    ("video/3gpp", "3gp"),
+    // Documents and archives
+    ("application/pdf", "pdf"),
+    ("text/plain", "txt"),
+    ("text/markdown", "md"),
+    ("text/csv", "csv"),
+    ("text/html", "html"),
+    ("application/json", "json"),
+    ("application/rtf", "rtf"),
+    ("application/epub+zip", "epub"),
+    ("application/msword", "doc"),
+    ("application/vnd.openxmlformats-officedocument.wordprocessingml.document", "docx"),
+    ("application/vnd.ms-excel", "xls"),
+    ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", "xlsx"),
+    ("application/vnd.ms-powerpoint", "ppt"),
+    ("application/vnd.openxmlformats-officedocument.presentationml.presentation", "pptx"),
+    ("application/vnd.oasis.opendocument.text", "odt"),
+    ("application/zip", "zip"),
+    ("application/gzip", "gz"),
+    ("application/x-7z-compressed", "7z"),
+    ("application/x-tar", "tar"),
+];
+
+/// Derives a file extension from a MIME type.
+///
+/// # Arguments
+///
+/// * `mime` - The MIME type; parameters such as `; charset=utf-8` are ignored.
+///
+/// # Returns
+///
+/// The usual extension (without a dot), or None if the type is unknown.
+pub fn extension_for_mime(mime: &str) -> Option<&'static str> {
+    let essence = mime.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+
+    MIME_EXTENSIONS
+        .iter()
+        .find(|(known, _)| *known == essence)
+        .map(|(_, extension)| *extension)
+        .or_else(|| mime_guess::get_mime_extensions_str(&essence)?.first().copied())
+}
+
 /**
  Infer a likely file extension using magical_rs only.
  Returns a common extension string (e.g. "png", "jpg") or None when unknown.
@@ -504,20 +3466,72 @@ fn infer_extension_from_bytes(bytes: &[u8]) -> Option<&'static str> {
     None
 }
 
+/// Returns whether the bytes sniff as a format that is already compressed.
+///
+/// Uncompressed formats like BMP, TIFF, WAV, ISO and tar still benefit from
+/// compression.
+fn is_compressed_format(bytes: &[u8]) -> bool {
+    infer_extension_from_bytes(bytes).is_some_and(|ext| {
+        !matches!(ext, "bmp" | "tiff" | "wav" | "iso" | "tar")
+    })
+}
+
 /// Creates a progress callback for file uploads.
 ///
+/// # Arguments
+///
+/// * `recipient` - The recipient of the file, included in log lines.
+///
 /// # Returns
 ///
 /// A boxed progress callback function.
-fn create_progress_callback() -> crate::upload::ProgressCallback {
+fn create_progress_callback(recipient: PublicKey) -> crate::upload::ProgressCallback {
     Box::new(move |percentage, _| {
         if let Some(pct) = percentage {
-            println!("Upload progress: {}%", pct);
+            debug!("Upload progress for {}: {}%", recipient, pct);
         }
         Ok(())
     })
 }
 
+/// Number of geohash characters sent with a location (roughly 5m accuracy)
+const GEOHASH_PRECISION: usize = 9;
+
+/// Encodes coordinates as a geohash of the given length.
+fn encode_geohash(lat: f64, lon: f64, precision: usize) -> String {
+    const BASE32: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut hash = String::with_capacity(precision);
+    let mut even_bit = true;
+    let (mut bits, mut index) = (0, 0usize);
+
+    while hash.len() < precision {
+        let (range, value) = if even_bit {
+            (&mut lon_range, lon)
+        } else {
+            (&mut lat_range, lat)
+        };
+        let mid = (range.0 + range.1) / 2.0;
+        index <<= 1;
+        if value >= mid {
+            index |= 1;
+            range.0 = mid;
+        } else {
+            range.1 = mid;
+        }
+        even_bit = !even_bit;
+
+        bits += 1;
+        if bits == 5 {
+            hash.push(BASE32[index] as char);
+            bits = 0;
+            index = 0;
+        }
+    }
+    hash
+}
+
 /// Gets the server configuration for file uploads.
 ///
 /// # Returns
@@ -532,6 +3546,7 @@ async fn get_server_config() -> Result<ServerConfig, String> {
         let conf = nostr_sdk::nips::nip96::get_server_config(url, None)
             .await
             .map_err(|e| e.to_string())?;
+        upload::validate_server_config(&conf).map_err(|e| e.to_string())?;
             PRIVATE_NIP96_CONFIG
                 .set(conf.clone())
                 .map_err(|_| "Failed to set server config")?;
@@ -539,92 +3554,393 @@ async fn get_server_config() -> Result<ServerConfig, String> {
     }
 }
 
-/// Uploads a file to the server with progress tracking.
+/// Uploads a file to the server with progress tracking.
+///
+/// # Arguments
+///
+/// * `signer` - The signer for authentication.
+/// * `conf` - The server configuration.
+/// * `proxy` - Optional SOCKS5 proxy for the upload client.
+/// * `file_data` - The file data to upload.
+/// * `mime_type` - The MIME type of the file.
+/// * `progress_callback` - The progress callback function.
+///
+/// # Returns
+///
+/// A Result containing the URL of the uploaded file.
+async fn upload_file(
+    signer: &Arc<dyn NostrSigner>,
+    conf: &ServerConfig,
+    proxy: Option<SocketAddr>,
+    file_data: &[u8],
+    mime_type: &str,
+    progress_callback: crate::upload::ProgressCallback,
+) -> Result<Url, String> {
+    let _retry_count = 3;
+    let _retry_spacing = std::time::Duration::from_secs(2);
+
+    let upload_config = upload::UploadConfig::default();
+    let upload_params = upload::UploadParams::default();
+
+    crate::upload::upload_data_with_progress(
+        signer,
+        conf,
+        file_data.to_vec(),
+        Some(mime_type),
+        proxy,
+        progress_callback,
+        Some(upload_params),
+        Some(upload_config),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Gift-wraps a rumor to the recipient and publishes it.
+///
+/// Waits for the rate limiter, then publishes to the given relays, or to all
+/// write relays when `relays` is empty. If the bot has an outbox, a send no
+/// relay accepts is queued for retry; only the signed gift wrap is kept, never
+/// the rumor. The conversation list only counts messages a relay accepted.
+///
+/// # Arguments
+///
+/// * `bot` - A reference to the VectorBot.
+/// * `recipient` - The recipient's public key.
+/// * `rumor` - The unsigned rumor to wrap.
+/// * `extra_tags` - Extra tags for the outer gift wrap event.
+/// * `relays` - Relay hints to publish to (empty for all write relays).
+///
+/// # Returns
+///
+/// A Result containing the outcome on each relay, or an error if no relay
+/// accepted the gift wrap and it was not queued. Relays of a queued send are
+/// reported as [`RelayStatus::Pending`]; use [`SendResult::accepted`] where a
+/// queued send must not count as delivered.
+async fn publish_rumor<I>(
+    bot: &VectorBot,
+    recipient: &PublicKey,
+    mut rumor: UnsignedEvent,
+    extra_tags: I,
+    relays: &[String],
+) -> Result<SendResult, String>
+where
+    I: IntoIterator<Item = Tag>,
+{
+    validate_public_key(recipient).map_err(|e| e.to_string())?;
+    if bot.inner.client_tag_in_dms {
+        if let Some(tag) = bot.client_tag() {
+            rumor.tags.push(tag);
+            rumor.id = None;
+            rumor.ensure_id();
+        }
+    }
+    bot.throttle().await;
+
+    // Expiring rumors such as typing indicators are not worth retrying late
+    let expiration = rumor.tags.expiration().copied();
+    let (kind, created_at) = (rumor.kind, rumor.created_at);
+    let gift_wrap = gift_wrap_with_window(
+        &bot.inner.signer,
+        recipient,
+        rumor,
+        extra_tags,
+        bot.inner.giftwrap_time_window,
+    )
+    .await
+    .map_err(|e| format!("Failed to build gift wrap: {e}"))?;
+
+    let delivered = deliver_gift_wrap(&bot.client, &bot.inner.relay_hints, &gift_wrap, relays).await;
+    if matches!(delivered, Ok(ref result) if result.is_accepted()) {
+        bot.record_conversation(*recipient, kind, created_at);
+    }
+    let Some(ref outbox) = bot.inner.outbox else {
+        let result = delivered?;
+        if !result.is_accepted() {
+            return Err(format!("No relay accepted gift wrap: {}", result.failure_summary()));
+        }
+        return Ok(result);
+    };
+
+    match delivered {
+        Ok(result) if result.is_accepted() => Ok(result),
+        Ok(result) => {
+            warn!("Queueing send to {recipient} for retry: {}", result.failure_summary());
+            outbox.push(gift_wrap, expiration, relays.to_vec());
+            Ok(result.into_pending())
+        }
+        Err(e) => {
+            warn!("Queueing send to {recipient} for retry: {e}");
+            let targets = if relays.is_empty() {
+                bot.client.relays().await.into_keys().collect()
+            } else {
+                relays.iter().filter_map(|r| RelayUrl::parse(r).ok()).collect()
+            };
+            let result = SendResult::pending(gift_wrap.id, targets);
+            outbox.push(gift_wrap, expiration, relays.to_vec());
+            Ok(result)
+        }
+    }
+}
+
+/// Checks that an incoming event is a gift wrap worth unwrapping.
+///
+/// Rejects other kinds, content longer than `max_size` and wraps without a
+/// recipient `p` tag, logging why at debug level.
+fn is_acceptable_gift_wrap(event: &Event, max_size: usize) -> bool {
+    if event.kind != Kind::GiftWrap {
+        return false;
+    }
+    if event.content.len() > max_size {
+        debug!(
+            "Dropping gift wrap {}: content is {} bytes, limit is {max_size}",
+            event.id,
+            event.content.len()
+        );
+        return false;
+    }
+    if event.tags.public_keys().next().is_none() {
+        debug!("Dropping gift wrap {}: no recipient tag", event.id);
+        return false;
+    }
+    true
+}
+
+/// Splits relay hints into those the bot's pool serves with `flag` and the rest.
+///
+/// # Arguments
+///
+/// * `client` - The bot's client.
+/// * `relays` - The relay URLs.
+/// * `flag` - [`RelayServiceFlags::READ`] or [`RelayServiceFlags::WRITE`].
+///
+/// # Returns
+///
+/// The URLs usable through `client`, and the URLs that have to be joined as
+/// temporary hints.
+async fn split_relay_hints(
+    client: &Client,
+    relays: &[String],
+    flag: RelayServiceFlags,
+) -> (Vec<String>, Vec<String>) {
+    let mut pooled = Vec::new();
+    let mut hinted = Vec::new();
+    for url in relays {
+        match client.relay(url).await {
+            Ok(relay) if relay.flags().has(flag, FlagCheck::All) => pooled.push(url.clone()),
+            _ => hinted.push(url.clone()),
+        }
+    }
+    (pooled, hinted)
+}
+
+/// Publishes a signed gift wrap to specific relays.
+///
+/// Relays the bot's pool can write to are used directly; the others are
+/// leased from `hints` for this send only.
+///
+/// # Arguments
+///
+/// * `client` - The bot's client.
+/// * `hints` - The pool for relays outside the bot's own.
+/// * `gift_wrap` - The signed gift wrap.
+/// * `relays` - The relays to publish to.
+///
+/// # Returns
+///
+/// A Result containing the outcome on each relay.
+async fn send_to_relays(
+    client: &Client,
+    hints: &RelayHints,
+    gift_wrap: &Event,
+    relays: &[String],
+) -> Result<Output<EventId>, nostr_sdk::client::Error> {
+    let (pooled, hinted) = split_relay_hints(client, relays, RelayServiceFlags::WRITE).await;
+    let mut output = Output {
+        val: gift_wrap.id,
+        success: HashSet::new(),
+        failed: HashMap::new(),
+    };
+
+    if !pooled.is_empty() {
+        let sent = client.send_event_to(pooled, gift_wrap).await?;
+        output.success.extend(sent.success);
+        output.failed.extend(sent.failed);
+    }
+    if !hinted.is_empty() {
+        let leased = hints.acquire(&hinted).await?;
+        let sent = hints.client().send_event_to(leased.clone(), gift_wrap).await;
+        hints.release(leased).await;
+        let sent = sent?;
+        output.success.extend(sent.success);
+        output.failed.extend(sent.failed);
+    }
+
+    Ok(output)
+}
+
+/// Publishes a signed gift wrap once, without throttling or queueing.
+///
+/// Relay hints the pool cannot write to are joined through `hints` only for
+/// this send, so they do not receive other sends.
+///
+/// # Arguments
+///
+/// * `client` - The client to publish with.
+/// * `hints` - The pool for relays outside the client's own.
+/// * `gift_wrap` - The signed gift wrap.
+/// * `relays` - Relay hints to publish to (empty for all write relays).
+///
+/// # Returns
+///
+/// A Result containing the outcome on each relay, or an error if the gift
+/// wrap could not be sent at all.
+pub(crate) async fn deliver_gift_wrap(
+    client: &Client,
+    hints: &RelayHints,
+    gift_wrap: &Event,
+    relays: &[String],
+) -> Result<SendResult, String> {
+    let result = if relays.is_empty() {
+        client.send_event(gift_wrap).await
+    } else {
+        send_to_relays(client, hints, gift_wrap, relays).await
+    };
+
+    match result {
+        Ok(output) => {
+            let result = SendResult::from(output);
+            if result.is_accepted() {
+                debug!(
+                    "Gift wrap {} accepted by {} of {} relays",
+                    gift_wrap.id,
+                    result.accepted_count(),
+                    result.relays.len()
+                );
+            } else {
+                error!(
+                    "No relay accepted gift wrap {}: {}",
+                    gift_wrap.id,
+                    result.failure_summary()
+                );
+            }
+            Ok(result)
+        }
+        Err(e) => {
+            error!("Error sending gift wrap {}: {:?}", gift_wrap.id, e);
+            Err(format!("Error sending gift wrap: {:?}", e))
+        }
+    }
+}
+
+/// Seals and gift-wraps a rumor with a custom timestamp randomization window.
+///
+/// Works like [`EventBuilder::gift_wrap`], which always randomizes the seal
+/// and wrap `created_at` up to two days into the past. A wider window hides
+/// when a message was sent more thoroughly. A narrower one keeps relays that
+/// filter by `since` from missing fresh messages.
 ///
 /// # Arguments
 ///
-/// * `keys` - The keys for authentication.
-/// * `conf` - The server configuration.
-/// * `file_data` - The file data to upload.
-/// * `mime_type` - The MIME type of the file.
-/// * `progress_callback` - The progress callback function.
+/// * `signer` - The sender's signer, used for the seal.
+/// * `receiver` - The recipient's public key.
+/// * `rumor` - The unsigned rumor to wrap.
+/// * `extra_tags` - Extra tags for the outer gift wrap event.
+/// * `time_window` - Maximum randomization into the past (zero for none).
 ///
 /// # Returns
 ///
-/// A Result containing the URL of the uploaded file.
-async fn upload_file(
-    keys: &Keys,
-    conf: &ServerConfig,
-    file_data: &[u8],
-    mime_type: &str,
-    progress_callback: crate::upload::ProgressCallback,
-) -> Result<Url, String> {
-    let _retry_count = 3;
-    let _retry_spacing = std::time::Duration::from_secs(2);
+/// A Result containing the signed gift wrap event.
+pub async fn gift_wrap_with_window<T, I>(
+    signer: &T,
+    receiver: &PublicKey,
+    rumor: UnsignedEvent,
+    extra_tags: I,
+    time_window: std::time::Duration,
+) -> Result<Event, VectorBotError>
+where
+    T: NostrSigner,
+    I: IntoIterator<Item = Tag>,
+{
+    let tweaked = || match time_window.as_secs() {
+        0 => Timestamp::now(),
+        secs => Timestamp::tweaked(0..secs),
+    };
 
-    let upload_config = upload::UploadConfig::default();
-    let upload_params = upload::UploadParams::default();
+    let seal = EventBuilder::seal(signer, receiver, rumor)
+        .await?
+        .custom_created_at(tweaked())
+        .sign(signer)
+        .await?;
 
-    crate::upload::upload_data_with_progress(
-        keys,
-        conf,
-        file_data.to_vec(),
-        Some(mime_type),
-        None,
-        progress_callback,
-        Some(upload_params),
-        Some(upload_config),
+    // The wrap is signed by a throwaway key so it cannot be linked to the sender
+    let ephemeral = Keys::generate();
+    let content = nip44::encrypt(
+        ephemeral.secret_key(),
+        receiver,
+        seal.as_json(),
+        nip44::Version::default(),
     )
-    .await
-    .map_err(|e| e.to_string())
+    .map_err(|e| VectorBotError::GenericError(e.to_string()))?;
+
+    let mut tags: Vec<Tag> = extra_tags.into_iter().collect();
+    tags.push(Tag::public_key(*receiver));
+
+    Ok(EventBuilder::new(Kind::GiftWrap, content)
+        .tags(tags)
+        .custom_created_at(tweaked())
+        .sign_with_keys(&ephemeral)?)
 }
 
-async fn send_nip25(bot: &VectorBot, recipient: &PublicKey, reference_id: String, message_type: Kind, emoji: String) -> Result<(), String> {
+async fn send_nip25(bot: &VectorBot, recipient: &PublicKey, relays: &[String], reference_id: String, message_type: Kind, emoji: String, tags: Vec<Tag>) -> Result<(), String> {
 
-    let reference_event = EventId::from_hex(reference_id.as_str()).unwrap();
+    let reference_event = EventId::from_hex(reference_id.as_str())
+        .map_err(|e| format!("Invalid reference event id: {e}"))?;
 
     let rumor = EventBuilder::reaction_extended(
         reference_event,
         *recipient,
         Some(message_type),
         &emoji,
-    );
+    )
+    .tags(tags);
 
-    let built_rumor = rumor.build(bot.keys.public_key());
+    let built_rumor = rumor.build(bot.inner.public_key);
 
-    match bot
-        .client
-        .gift_wrap(recipient, built_rumor.clone(), [],)
+    publish_rumor(bot, recipient, built_rumor, [], relays)
         .await
-    {
-        Ok(output) => {
-            if output.success.is_empty() && !output.failed.is_empty() {
-                error!("Failed to send attachment rumor: {:?}", output);
-                return Err("Failed to send attachment rumor".to_string());
-            }
-            Ok(())
-        }
-        Err(e) => {
-            error!("Error sending attachment rumor: {:?}", e);
-            Err(format!("Error sending attachment rumor: {:?}", e))
-        }
-    }
+        .and_then(SendResult::accepted)
+        .map(|_| ())
 
 }
 
-async fn send_kind30078(bot: &VectorBot, recipient: &PublicKey, content: String, expiration: Timestamp)-> Result<(), String> {
+async fn send_nip09(
+    bot: &VectorBot,
+    recipient: &PublicKey,
+    relays: &[String],
+    event_id: EventId,
+    deleted_kind: Kind,
+) -> Result<(), String> {
+    let rumor = EventBuilder::delete(EventDeletionRequest::new().id(event_id))
+        .tag(Tag::public_key(*recipient))
+        .tag(Tag::custom(TagKind::k(), [deleted_kind.as_u16().to_string()]))
+        .tag(current_ms_tag());
+
+    let built_rumor = rumor.build(bot.inner.public_key);
 
-    // Build and broadcast the Typing Indicator
-    // Add millisecond precision tag so clients can order messages sent within the same second
-    let final_time = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap();
-    let milliseconds = final_time.as_millis() % 1000;
+    publish_rumor(bot, recipient, built_rumor, [], relays)
+        .await
+        .and_then(SendResult::accepted)
+        .map(|_| ())
+}
+
+async fn send_kind30078(bot: &VectorBot, recipient: &PublicKey, relays: &[String], content: String, expiration: Timestamp)-> Result<(), String> {
 
+    // Build and broadcast the Typing Indicator
     let rumor = EventBuilder::new(Kind::ApplicationSpecificData, content)
         .tag(Tag::public_key(*recipient))
         .tag(Tag::custom(TagKind::d(), vec!["vector"]))
-        .tag(Tag::custom(TagKind::custom("ms"), [milliseconds.to_string()]))
+        .tag(current_ms_tag())
         .tag(Tag::expiration(expiration));
 
     // This expiration time is for NIP-40 relays so they can purge old Typing Indicators
@@ -636,25 +3952,12 @@ async fn send_kind30078(bot: &VectorBot, recipient: &PublicKey, content: String,
             + 3600,
     );
 
-    let built_rumor = rumor.build(bot.keys.public_key());
+    let built_rumor = rumor.build(bot.inner.public_key);
 
-    match bot
-        .client
-        .gift_wrap(recipient, built_rumor.clone(), [Tag::expiration(expiry_time)],)
+    publish_rumor(bot, recipient, built_rumor, [Tag::expiration(expiry_time)], relays)
         .await
-    {
-        Ok(output) => {
-            if output.success.is_empty() && !output.failed.is_empty() {
-                error!("Failed to send attachment rumor: {:?}", output);
-                return Err("Failed to send attachment rumor".to_string());
-            }
-            Ok(())
-        }
-        Err(e) => {
-            error!("Error sending attachment rumor: {:?}", e);
-            Err(format!("Error sending attachment rumor: {:?}", e))
-        }
-    }
+        .and_then(SendResult::accepted)
+        .map(|_| ())
 
 }
 
@@ -665,32 +3968,31 @@ async fn send_kind30078(bot: &VectorBot, recipient: &PublicKey, content: String,
 ///
 /// * `bot` - A reference to the VectorBot.
 /// * `recipient` - The recipient's public key.
+/// * `relays` - Relay hints to publish to (empty for all write relays).
 /// * `url` - The URL of the uploaded file.
 /// * `file` - A reference to the AttachmentFile.
 /// * `params` - A reference to the encryption parameters.
 /// * `file_hash` - The hash of the file.
 /// * `file_size` - The size of the file.
 /// * `mime_type` - The MIME type of the file.
+/// * `options` - Extra options such as a caption.
 ///
 /// # Returns
 ///
 /// A Result indicating success or failure.
+#[allow(clippy::too_many_arguments)]
 async fn send_attachment_rumor(
     bot: &VectorBot,
     recipient: &PublicKey,
+    relays: &[String],
     url: &Url,
     file: &AttachmentFile,
     params: &crypto::EncryptionParams,
     file_hash: &str,
     file_size: usize,
     mime_type: &str,
+    options: &FileSendOptions,
 ) -> Result<(), String> {
-    // Add millisecond precision tag so clients can order messages sent within the same second
-    let final_time = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap();
-    let milliseconds = final_time.as_millis() % 1000;
-
     // Create the attachment rumor
     let mut attachment_rumor = EventBuilder::new(Kind::from_u16(15), url.to_string())
         .tag(Tag::public_key(*recipient))
@@ -703,16 +4005,71 @@ async fn send_attachment_rumor(
             TagKind::custom("encryption-algorithm"),
             ["aes-gcm"],
         ))
-        .tag(Tag::custom(
-            TagKind::custom("decryption-key"),
-            [params.key.as_str()],
-        ))
-        .tag(Tag::custom(
-            TagKind::custom("decryption-nonce"),
-            [params.nonce.as_str()],
-        ))
         .tag(Tag::custom(TagKind::custom("ox"), [file_hash]))
-        .tag(Tag::custom(TagKind::custom("ms"), [milliseconds.to_string()]));
+        .tag(current_ms_tag());
+
+    // Attach the decryption parameters using the configured scheme
+    attachment_rumor = match bot.inner.attachment_key_scheme {
+        AttachmentKeyScheme::Tags => attachment_rumor
+            .tag(Tag::custom(
+                TagKind::custom("decryption-key"),
+                [params.key.as_str()],
+            ))
+            .tag(Tag::custom(
+                TagKind::custom("decryption-nonce"),
+                [params.nonce.as_str()],
+            )),
+        AttachmentKeyScheme::Nip44 => {
+            let json = serde_json::to_string(params)
+                .map_err(|e| format!("Failed to serialize attachment key: {}", e))?;
+            let payload = bot
+                .inner
+                .signer
+                .nip44_encrypt(recipient, &json)
+                .await
+                .map_err(|e| format!("Failed to encrypt attachment key: {}", e))?;
+            attachment_rumor
+                .tag(Tag::custom(
+                    TagKind::custom("key-scheme"),
+                    [AttachmentKeyScheme::Nip44.tag_value()],
+                ))
+                .tag(Tag::custom(TagKind::custom("decryption-params"), [payload]))
+        }
+    };
+
+    // Carry the caption in the same rumor so it arrives with the file
+    if let Some(ref caption) = options.caption {
+        attachment_rumor =
+            attachment_rumor.tag(Tag::custom(TagKind::custom("caption"), [caption.as_str()]));
+    }
+
+    // Voice notes carry their duration so clients can render a player up front
+    if let Some(duration) = options.voice_duration {
+        attachment_rumor =
+            attachment_rumor.tag(Tag::custom(TagKind::custom("voice"), [duration.to_string()]));
+    }
+
+    // Let the receiver hide sensitive files behind a warning
+    if options.sensitive {
+        attachment_rumor = attachment_rumor
+            .tag(Tag::from_standardized(TagStandard::ContentWarning { reason: None }));
+    }
+
+    // Tell the receiver which tags were used as associated data
+    if bot.inner.bind_attachment_metadata {
+        attachment_rumor = attachment_rumor.tag(Tag::custom(
+            TagKind::custom(crypto::ATTACHMENT_AAD_TAG),
+            [crypto::ATTACHMENT_AAD_VALUE],
+        ));
+    }
+
+    // Tell the receiver to decompress after decrypting
+    if let Some(compression) = file.compression {
+        attachment_rumor = attachment_rumor.tag(Tag::custom(
+            TagKind::custom("compression"),
+            [compression.tag_value()],
+        ));
+    }
 
     // Append image metadata if available
     if let Some(ref img_meta) = file.img_meta {
@@ -725,29 +4082,145 @@ async fn send_attachment_rumor(
                 TagKind::custom("dim"),
                 [format!("{}x{}", img_meta.width, img_meta.height)],
             ));
+    } else if let Some((width, height)) = probe_image_dimensions(&file.bytes) {
+        // Without precomputed metadata, the header alone gives the dimensions
+        attachment_rumor =
+            attachment_rumor.tag(Tag::custom(TagKind::custom("dim"), [format!("{width}x{height}")]));
     }
 
-    let built_rumor = attachment_rumor.build(bot.keys.public_key());
+    let built_rumor = attachment_rumor.build(bot.inner.public_key);
 
-    debug!("Sending attachment rumor: {:?}", built_rumor);
+    // The rumor carries the decryption key, so only its id is logged
+    debug!("Sending attachment rumor {:?} to {}", built_rumor.id, recipient);
 
-    match bot
-        .client
-        .gift_wrap(recipient, built_rumor.clone(), [])
+    publish_rumor(bot, recipient, built_rumor, [], relays)
         .await
+        .and_then(SendResult::accepted)
+        .map(|_| ())
+}
+
+/// Builds the `ms` tag carrying the current millisecond within the second.
+///
+/// Clients use it to order messages sent within the same second, since
+/// `created_at` only has second precision.
+fn current_ms_tag() -> Tag {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap();
+    Tag::custom(TagKind::custom("ms"), [now.subsec_millis().to_string()])
+}
+
+/// Reads the millisecond ordering tag from a rumor.
+///
+/// # Arguments
+///
+/// * `rumor` - The unwrapped rumor.
+///
+/// # Returns
+///
+/// The millisecond (0-999) from the `ms` tag, or None if it is missing or invalid.
+pub fn message_subsecond(rumor: &UnsignedEvent) -> Option<u16> {
+    message::tag_value(&rumor.tags, "ms")
+        .and_then(|ms| ms.parse::<u16>().ok())
+        .filter(|ms| *ms < 1000)
+}
+
+/// Reads the NIP-17 subject from a rumor.
+///
+/// # Arguments
+///
+/// * `rumor` - The unwrapped rumor.
+///
+/// # Returns
+///
+/// The value of the `subject` tag, or None if the rumor has no subject.
+pub fn message_subject(rumor: &UnsignedEvent) -> Option<String> {
+    rumor
+        .tags
+        .iter()
+        .find(|tag| tag.kind() == TagKind::Subject)
+        .and_then(|tag| tag.content())
+        .map(str::to_string)
+}
+
+/// Reads a receipt sent with [`Channel::send_receipt`].
+///
+/// For receipts covering several messages, this returns the first one; use
+/// [`parse_receipts`] to get them all.
+///
+/// # Arguments
+///
+/// * `rumor` - The unwrapped rumor.
+///
+/// # Returns
+///
+/// The id of the receipted message and the kind of receipt, or None if the
+/// rumor is not a receipt.
+pub fn parse_receipt(rumor: &UnsignedEvent) -> Option<(EventId, ReceiptKind)> {
+    let (event_ids, kind) = parse_receipts(rumor)?;
+    Some((event_ids[0], kind))
+}
+
+/// Reads every message acknowledged by a receipt.
+///
+/// # Arguments
+///
+/// * `rumor` - The unwrapped rumor.
+///
+/// # Returns
+///
+/// The ids of the receipted messages and the kind of receipt, or None if
+/// the rumor is not a receipt.
+pub fn parse_receipts(rumor: &UnsignedEvent) -> Option<(Vec<EventId>, ReceiptKind)> {
+    if rumor.kind != Kind::ApplicationSpecificData
+        || rumor.tags.identifier() != Some(RECEIPT_IDENTIFIER)
     {
-        Ok(output) => {
-            if output.success.is_empty() && !output.failed.is_empty() {
-                error!("Failed to send attachment rumor: {:?}", output);
-                return Err("Failed to send attachment rumor".to_string());
-            }
-            Ok(())
-        }
-        Err(e) => {
-            error!("Error sending attachment rumor: {:?}", e);
-            Err(format!("Error sending attachment rumor: {:?}", e))
-        }
+        return None;
+    }
+    let kind = ReceiptKind::from_content(&rumor.content)?;
+    let event_ids: Vec<EventId> = rumor.tags.event_ids().copied().collect();
+    if event_ids.is_empty() {
+        return None;
+    }
+    Some((event_ids, kind))
+}
+
+/// Returns true if a rumor carries a NIP-36 `content-warning` tag.
+///
+/// Clients should blur or hide such attachments until the user chooses to
+/// view them.
+///
+/// # Arguments
+///
+/// * `rumor` - The unwrapped rumor, e.g. [`UnwrappedGift::rumor`].
+///
+/// # Returns
+///
+/// `true` if the sender marked the content as sensitive.
+pub fn is_sensitive(rumor: &UnsignedEvent) -> bool {
+    rumor.tags.find(TagKind::ContentWarning).is_some()
+}
+
+/// Checks that a public key can actually receive messages.
+///
+/// [`PublicKey::from_byte_array`] skips validation, so keys built from raw
+/// bytes may be all zeros or not lie on the curve.
+///
+/// # Arguments
+///
+/// * `public_key` - The key to check.
+///
+/// # Returns
+///
+/// Ok if the key is usable, or [`VectorBotError::InvalidPublicKey`].
+pub fn validate_public_key(public_key: &PublicKey) -> Result<(), VectorBotError> {
+    if public_key.as_bytes().iter().all(|byte| *byte == 0) {
+        return Err(VectorBotError::InvalidPublicKey("key is all zeros".into()));
     }
+    public_key
+        .xonly()
+        .map_err(|e| VectorBotError::InvalidPublicKey(format!("{public_key}: {e}")))?;
+    Ok(())
 }
 
 /// Calculate SHA-256 hash of file data
@@ -757,6 +4230,61 @@ pub fn calculate_file_hash(data: &[u8]) -> String {
     hex::encode(hasher.finalize())
 }
 
+/// Chunk size used when hashing files from disk
+const FILE_HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Calculate the SHA-256 hash of a file on disk without loading it whole.
+///
+/// Produces the same hex digest as [`calculate_file_hash`], so it can be used
+/// for the `ox` tag of files too large to keep in memory.
+///
+/// # Arguments
+///
+/// * `path` - The file to hash.
+///
+/// # Returns
+///
+/// A Result containing the hex digest, or an I/O error if the file could
+/// not be read.
+pub fn hash_file_path(path: &std::path::Path) -> std::io::Result<String> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; FILE_HASH_CHUNK_SIZE];
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Verify decrypted attachment bytes against the `ox` tag carried in the rumor.
+///
+/// The comparison runs in constant time so a mismatch does not leak how much
+/// of the hash matched.
+pub fn verify_attachment(plaintext: &[u8], expected_ox: &str) -> bool {
+    let expected = match hex::decode(expected_ox.trim()) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+    let actual = Sha256::digest(plaintext);
+    if expected.len() != actual.len() {
+        return false;
+    }
+
+    expected
+        .iter()
+        .zip(actual.iter())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
 /// Represents metadata about an image file.
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
 pub struct ImageMetadata {
@@ -768,6 +4296,85 @@ pub struct ImageMetadata {
     pub height: u32,
 }
 
+impl ImageMetadata {
+    /// Decode the Blurhash into RGBA pixels so clients can render a placeholder.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the placeholder to render, in pixels.
+    /// * `height` - Height of the placeholder to render, in pixels.
+    ///
+    /// # Returns
+    ///
+    /// `width * height * 4` RGBA bytes, or an empty buffer if the Blurhash is invalid.
+    pub fn decode_blurhash(&self, width: u32, height: u32) -> Vec<u8> {
+        blurhash::decode(&self.blurhash, width, height, 1.0).unwrap_or_default()
+    }
+}
+
+/// Compute a Blurhash for image bytes, or None if they are not a decodable image.
+///
+/// The image is thumbnailed before encoding since a Blurhash only captures
+/// low frequencies; hashing the full-resolution pixels would be wasted work.
+fn generate_blurhash(bytes: &[u8]) -> Option<String> {
+    ::image::guess_format(bytes).ok()?;
+    let image = ::image::load_from_memory(bytes).ok()?;
+    blurhash_for_image(&image)
+}
+
+/// Read an image's dimensions from its header without decoding the pixels.
+///
+/// Returns None if the bytes are not a recognised image.
+fn probe_image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    ::image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Compute a Blurhash from an already decoded image.
+fn blurhash_for_image(image: &::image::DynamicImage) -> Option<String> {
+    let thumbnail = image.thumbnail(64, 64).to_rgba8();
+    blurhash::encode(
+        4,
+        3,
+        thumbnail.width(),
+        thumbnail.height(),
+        thumbnail.as_raw(),
+    )
+    .ok()
+}
+
+/// Build ImageMetadata (Blurhash and dimensions) for a decoded image.
+fn image_metadata(image: &::image::DynamicImage) -> Option<ImageMetadata> {
+    Some(ImageMetadata {
+        blurhash: blurhash_for_image(image)?,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+/// Encode an image as JPEG, or PNG when it has an alpha channel.
+///
+/// Returns the encoded bytes and the matching file extension.
+fn encode_image(image: &::image::DynamicImage, quality: u8) -> Option<(Vec<u8>, &'static str)> {
+    let mut bytes = Vec::new();
+    if image.color().has_alpha() {
+        image
+            .write_to(&mut std::io::Cursor::new(&mut bytes), ::image::ImageFormat::Png)
+            .ok()?;
+        Some((bytes, "png"))
+    } else {
+        let encoder = ::image::codecs::jpeg::JpegEncoder::new_with_quality(
+            &mut bytes,
+            quality.clamp(1, 100),
+        );
+        image.to_rgb8().write_with_encoder(encoder).ok()?;
+        Some((bytes, "jpg"))
+    }
+}
+
 /// Represents a file attachment with metadata.
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct AttachmentFile {
@@ -777,32 +4384,40 @@ pub struct AttachmentFile {
     pub img_meta: Option<ImageMetadata>,
     /// The file extension
     pub extension: String,
+    /// Compression applied to `bytes`, if any
+    #[serde(default)]
+    pub compression: Option<CompressionAlgorithm>,
+    /// SHA-256 of the bytes before compression, used for the `ox` tag
+    #[serde(default)]
+    pub original_hash: Option<String>,
 }
 
-/// Load a file from disk into an AttachmentFile, using mime_guess to infer a sensible extension
-/// when the path has none or is unknown.
+/// Load a file from disk into an AttachmentFile.
+///
+/// The path's extension is used when it maps to a known MIME type. When it is
+/// missing or unknown (including `bin`), the content is sniffed the same way
+/// [`AttachmentFile::from_bytes`] does before falling back to `bin`.
 pub fn load_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<AttachmentFile> {
     let path_ref = path.as_ref();
 
     // Read bytes from disk
     let bytes = std::fs::read(path_ref)?;
 
-    // Prefer filesystem extension; if absent/invalid, derive from MIME guess
-    let extension = path_ref
-        .extension()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_string())
-        .or_else(|| {
-            let mime = mime_guess::from_path(path_ref).first_or_octet_stream();
-            mime_guess::get_mime_extensions(&mime)
-                .and_then(|arr| arr.first().map(|e| (*e).to_string()))
-        })
-        .unwrap_or_else(|| "bin".to_string());
+    // Prefer a recognised filesystem extension, then the sniffed content type
+    let path_extension = path_ref.extension().and_then(|s| s.to_str());
+    let extension = path_extension
+        .filter(|ext| mime_type_for_extension(ext) != "application/octet-stream")
+        .or_else(|| infer_extension_from_bytes(&bytes))
+        .or(path_extension)
+        .unwrap_or("bin")
+        .to_string();
 
     Ok(AttachmentFile {
         bytes,
         img_meta: None,
         extension,
+        compression: None,
+        original_hash: None,
     })
 }
 
@@ -824,6 +4439,198 @@ impl AttachmentFile {
             bytes: bytes_vec,
             img_meta: None,
             extension: ext,
+            compression: None,
+            original_hash: None,
+        }
+    }
+
+    /// Create an AttachmentFile from an HTTP response body.
+    ///
+    /// The extension comes from the `Content-Type` header; when that is
+    /// missing or generic, the bytes are sniffed as in
+    /// [`AttachmentFile::from_bytes`].
+    ///
+    /// # Arguments
+    ///
+    /// * `response` - The response to read, e.g. from `reqwest::get`.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the file, or an error if the request failed or
+    /// the body could not be read.
+    pub async fn from_response(response: reqwest::Response) -> Result<Self, VectorBotError> {
+        let status = response.status();
+        if !status.is_success() {
+            return Err(download::DownloadError::HttpStatus(status).into());
+        }
+
+        let extension = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .filter(|mime| !mime.starts_with("application/octet-stream"))
+            .and_then(extension_for_mime);
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(download::DownloadError::from)?
+            .to_vec();
+        let extension = extension
+            .or_else(|| infer_extension_from_bytes(&bytes))
+            .unwrap_or("bin")
+            .to_string();
+
+        Ok(Self {
+            bytes,
+            img_meta: None,
+            extension,
+            compression: None,
+            original_hash: None,
+        })
+    }
+
+    /// Downscales and re-encodes an oversized image before upload.
+    ///
+    /// Images whose longest side exceeds `max_dimension` are resized to fit,
+    /// preserving aspect ratio, then re-encoded as JPEG at `quality` (or PNG
+    /// when the image has transparency). The extension and image metadata are
+    /// updated to match. Non-images and images already within bounds are
+    /// returned unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_dimension` - Maximum width or height in pixels.
+    /// * `quality` - JPEG quality from 1 to 100.
+    ///
+    /// # Returns
+    ///
+    /// The (possibly) compressed AttachmentFile.
+    pub fn compress_image(self, max_dimension: u32, quality: u8) -> Self {
+        // Check the header first so images within bounds are never decoded
+        match probe_image_dimensions(&self.bytes) {
+            Some((width, height)) if width.max(height) > max_dimension => {}
+            _ => return self,
+        }
+        // Decoding drops the EXIF orientation, so apply it to the pixels
+        let image = match media::load_upright(&self.bytes) {
+            Some(image) => image,
+            None => return self,
+        };
+
+        let resized = image.resize(
+            max_dimension,
+            max_dimension,
+            ::image::imageops::FilterType::Lanczos3,
+        );
+        match encode_image(&resized, quality) {
+            Some((bytes, extension)) => Self {
+                bytes,
+                img_meta: image_metadata(&resized),
+                extension: extension.to_string(),
+                compression: None,
+                original_hash: None,
+            },
+            None => self,
+        }
+    }
+
+    /// Compresses the bytes before encryption, for large text or JSON files.
+    ///
+    /// The rumor carries a `compression` tag so the receiver decompresses
+    /// after decrypting (see [`message::FileMessage::decrypt`]). The hash of
+    /// the uncompressed bytes is kept in `original_hash`, so the `ox` tag
+    /// still matches what the receiver ends up with. Media and archives that
+    /// sniff as already compressed are left unchanged, as is any file that
+    /// would not get smaller.
+    ///
+    /// # Arguments
+    ///
+    /// * `algo` - The compression algorithm to use.
+    ///
+    /// # Returns
+    ///
+    /// The (possibly) compressed AttachmentFile.
+    pub fn compressed(self, algo: CompressionAlgorithm) -> Self {
+        if self.compression.is_some() || is_compressed_format(&self.bytes) {
+            return self;
+        }
+
+        let bytes = match algo {
+            CompressionAlgorithm::Deflate => media::deflate(&self.bytes),
+        };
+        match bytes {
+            Ok(bytes) if bytes.len() < self.bytes.len() => Self {
+                original_hash: Some(calculate_file_hash(&self.bytes)),
+                bytes,
+                compression: Some(algo),
+                ..self
+            },
+            Ok(_) => self,
+            Err(e) => {
+                warn!("Failed to compress attachment: {e}");
+                self
+            }
+        }
+    }
+
+    /// Removes EXIF, GPS and other embedded metadata from image bytes.
+    ///
+    /// JPEG and PNG files are rewritten losslessly; other supported image
+    /// formats, and files too damaged to rewrite, are re-encoded. Photos
+    /// with an EXIF orientation are rotated upright first so they do not
+    /// display sideways.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the AttachmentFile with metadata removed, or
+    /// [`VectorBotError::MetadataStripError`] if the bytes are not an image
+    /// that can be decoded, such as HEIC, AVIF or a truncated file.
+    pub fn strip_metadata(self) -> Result<Self, VectorBotError> {
+        // A compressed file is cleaned as plaintext and compressed again
+        if let Some(algo) = self.compression {
+            let plaintext = match algo {
+                CompressionAlgorithm::Deflate => media::inflate(&self.bytes, MAX_DECOMPRESSED_SIZE),
+            }
+            .map_err(|e| VectorBotError::MetadataStripError(e.to_string()))?;
+            let file = Self {
+                bytes: plaintext,
+                compression: None,
+                original_hash: None,
+                ..self
+            };
+            return Ok(file.strip_metadata()?.compressed(algo));
+        }
+
+        let reoriented = media::exif_orientation(&self.bytes).is_some();
+        let Some(bytes) = media::strip_metadata(&self.bytes) else {
+            return Err(VectorBotError::MetadataStripError(format!(
+                "cannot remove metadata from this .{} file",
+                self.extension
+            )));
+        };
+
+        // Rotating the pixels invalidates stored dimensions and blurhash
+        let img_meta = match self.img_meta {
+            Some(_) if reoriented => ::image::load_from_memory(&bytes)
+                .ok()
+                .and_then(|image| image_metadata(&image)),
+            img_meta => img_meta,
+        };
+        Ok(Self {
+            bytes,
+            img_meta,
+            ..self
+        })
+    }
+
+    /// Returns a Blurhash preview for list UIs.
+    ///
+    /// Uses the stored image metadata when present, otherwise computes one on
+    /// demand if the bytes sniff as an image. Returns None for non-images.
+    pub fn preview_blurhash(&self) -> Option<String> {
+        if let Some(ref img_meta) = self.img_meta {
+            return Some(img_meta.blurhash.clone());
         }
+        generate_blurhash(&self.bytes)
     }
 }
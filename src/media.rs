@@ -0,0 +1,185 @@
+use ::image::metadata::Orientation;
+use ::image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{Cursor, Read, Write};
+
+/// JPEG quality used when an image has to be re-encoded to fix its orientation
+const REORIENTED_JPEG_QUALITY: u8 = 90;
+
+/// Removes embedded metadata (EXIF, GPS, XMP, comments) from image bytes.
+///
+/// JPEG and PNG files are rewritten losslessly by dropping metadata segments
+/// and chunks. Other formats, and JPEG or PNG files too damaged to rewrite,
+/// are decoded and re-encoded, which discards any metadata. Images with an
+/// EXIF orientation are rotated upright and re-encoded first, since they
+/// would otherwise display sideways once the flag is gone. Returns None when
+/// no clean copy can be produced, e.g. for formats the `image` crate cannot
+/// decode (HEIC, AVIF) or undecodable files, so callers can refuse to send
+/// the original.
+pub(crate) fn strip_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+    let format = ::image::guess_format(bytes).ok()?;
+    if exif_orientation(bytes).is_some() {
+        return reencode_upright(bytes, format);
+    }
+
+    let stripped = match format {
+        ImageFormat::Jpeg => strip_jpeg(bytes),
+        ImageFormat::Png => strip_png(bytes),
+        // GIF carries no EXIF, and re-encoding would drop animation frames
+        ImageFormat::Gif => Some(bytes.to_vec()),
+        _ => None,
+    };
+    stripped.or_else(|| reencode_upright(bytes, format))
+}
+
+/// Returns the EXIF orientation of an image, or None if it is already upright.
+pub(crate) fn exif_orientation(bytes: &[u8]) -> Option<Orientation> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    match decoder.orientation().ok()? {
+        Orientation::NoTransforms => None,
+        orientation => Some(orientation),
+    }
+}
+
+/// Decodes an image and rotates or flips it as its EXIF orientation asks.
+pub(crate) fn load_upright(bytes: &[u8]) -> Option<DynamicImage> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder).ok()?;
+    image.apply_orientation(orientation);
+    Some(image)
+}
+
+/// Re-encodes an image upright in its original format, without metadata.
+///
+/// Returns None if the image cannot be decoded, or `format` cannot be encoded.
+fn reencode_upright(bytes: &[u8], format: ImageFormat) -> Option<Vec<u8>> {
+    let image = load_upright(bytes)?;
+    let mut out = Vec::new();
+    if format == ImageFormat::Jpeg {
+        let encoder = ::image::codecs::jpeg::JpegEncoder::new_with_quality(
+            &mut out,
+            REORIENTED_JPEG_QUALITY,
+        );
+        image.to_rgb8().write_with_encoder(encoder).ok()?;
+    } else {
+        image.write_to(&mut Cursor::new(&mut out), format).ok()?;
+    }
+    Some(out)
+}
+
+/// Drops APP1 (EXIF/XMP), APP13 (IPTC) and COM segments from a JPEG.
+///
+/// APP0 (JFIF) and APP2 (ICC colour profile) are kept so the image still
+/// renders with the right colours.
+fn strip_jpeg(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() < 4 || bytes[0] != 0xFF || bytes[1] != 0xD8 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&bytes[..2]);
+    let mut pos = 2;
+
+    while pos + 4 <= bytes.len() {
+        if bytes[pos] != 0xFF {
+            return None;
+        }
+        let marker = bytes[pos + 1];
+
+        // Start of scan: the rest is entropy-coded image data
+        if marker == 0xDA {
+            out.extend_from_slice(&bytes[pos..]);
+            return Some(out);
+        }
+
+        // Standalone markers carry no length field
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            out.extend_from_slice(&bytes[pos..pos + 2]);
+            pos += 2;
+            continue;
+        }
+
+        let length = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let end = pos + 2 + length;
+        if length < 2 || end > bytes.len() {
+            return None;
+        }
+
+        let is_metadata = matches!(marker, 0xE1 | 0xED | 0xFE);
+        if !is_metadata {
+            out.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+    }
+
+    // No scan data found; keep whatever trailing bytes remain
+    out.extend_from_slice(&bytes[pos..]);
+    Some(out)
+}
+
+/// Drops eXIf, tEXt, zTXt, iTXt and tIME chunks from a PNG.
+fn strip_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    const SIGNATURE_LEN: usize = 8;
+    if bytes.len() < SIGNATURE_LEN {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&bytes[..SIGNATURE_LEN]);
+    let mut pos = SIGNATURE_LEN;
+
+    while pos + 12 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[pos..pos + 4].try_into().ok()?) as usize;
+        let chunk_type = &bytes[pos + 4..pos + 8];
+        // length + type + data + crc
+        let end = pos.checked_add(12)?.checked_add(length)?;
+        if end > bytes.len() {
+            return None;
+        }
+
+        let is_metadata = matches!(chunk_type, b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME");
+        if !is_metadata {
+            out.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+
+        if chunk_type == b"IEND" {
+            break;
+        }
+    }
+
+    Some(out)
+}
+
+/// Compresses bytes with raw DEFLATE (RFC 1951).
+pub(crate) fn deflate(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
+/// Decompresses raw DEFLATE bytes, refusing output larger than `max` bytes.
+pub(crate) fn inflate(bytes: &[u8], max: usize) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    DeflateDecoder::new(bytes)
+        .take(max as u64 + 1)
+        .read_to_end(&mut out)?;
+    if out.len() > max {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("decompressed data exceeds {max} bytes"),
+        ));
+    }
+    Ok(out)
+}
@@ -0,0 +1,309 @@
+use crate::crypto::{self, CryptoError, EncryptionParams};
+use crate::{AttachmentKeyScheme, CompressionAlgorithm};
+use log::warn;
+use nostr_sdk::prelude::*;
+
+/// A private message decoded from an unwrapped gift wrap.
+///
+/// Every variant carries the sender's public key and the rumor's event id so
+/// handlers can reply, react or send receipts without keeping the gift around.
+#[derive(Debug, Clone)]
+pub enum DecodedMessage {
+    /// A NIP-17 text message (kind 14)
+    Text(TextMessage),
+    /// An encrypted file attachment (kind 15)
+    File(FileMessage),
+    /// Any other rumor kind, passed through untouched
+    Other(UnwrappedGift),
+}
+
+/// A decoded text message.
+#[derive(Debug, Clone)]
+pub struct TextMessage {
+    /// The public key of the sender
+    pub sender: PublicKey,
+    /// The id of the rumor
+    pub event_id: EventId,
+    /// The message text
+    pub content: String,
+    /// When the rumor was created
+    pub created_at: Timestamp,
+}
+
+/// A reaction to a message, decoded from a kind 7 rumor.
+#[derive(Debug, Clone)]
+pub struct ReactionEvent {
+    /// The public key of the reacting user
+    pub sender: PublicKey,
+    /// The id of the reaction rumor
+    pub event_id: EventId,
+    /// The id of the message being reacted to
+    pub target: EventId,
+    /// The reaction itself, usually an emoji or `+`
+    pub content: String,
+    /// When the rumor was created
+    pub created_at: Timestamp,
+}
+
+impl ReactionEvent {
+    /// Reads a reaction from an unwrapped rumor.
+    ///
+    /// # Arguments
+    ///
+    /// * `sender` - The sender of the gift wrap.
+    /// * `rumor` - The unwrapped rumor.
+    ///
+    /// # Returns
+    ///
+    /// The reaction, or None if the rumor is not a kind 7 reaction with an
+    /// `e` tag.
+    pub fn from_rumor(sender: PublicKey, rumor: &UnsignedEvent) -> Option<Self> {
+        if rumor.kind != Kind::Reaction {
+            return None;
+        }
+        // NIP-25: the last `e` tag is the event being reacted to
+        let target = rumor.tags.event_ids().last().copied()?;
+        Some(Self {
+            sender,
+            event_id: rumor.id.unwrap_or_else(|| rumor.clone().id()),
+            target,
+            content: rumor.content.clone(),
+            created_at: rumor.created_at,
+        })
+    }
+}
+
+/// A decoded file attachment.
+///
+/// The file itself still has to be downloaded from `url` and decrypted with
+/// `params`.
+#[derive(Debug, Clone)]
+pub struct FileMessage {
+    /// The public key of the sender
+    pub sender: PublicKey,
+    /// The id of the rumor
+    pub event_id: EventId,
+    /// Where the encrypted file is hosted
+    pub url: String,
+    /// The MIME type from the `file-type` tag
+    pub mime_type: Option<String>,
+    /// The encrypted size in bytes from the `size` tag
+    pub size: Option<u64>,
+    /// The SHA-256 of the plaintext from the `ox` tag (taken before
+    /// decompression when `compression` is set)
+    pub hash: Option<String>,
+    /// The AES-GCM key and nonce, if they could be recovered
+    pub params: Option<EncryptionParams>,
+    /// Associated data the ciphertext is bound to, if the sender used any
+    pub aad: Option<Vec<u8>>,
+    /// The image blurhash, if one was sent
+    pub blurhash: Option<String>,
+    /// The image dimensions as (width, height), if they were sent
+    pub dimensions: Option<(u32, u32)>,
+    /// Text sent alongside the file, if any
+    pub caption: Option<String>,
+    /// The `compression` tag, if the sender compressed the file before encrypting
+    pub compression: Option<String>,
+    /// The duration in seconds from the `voice` tag, if this is a voice note
+    pub voice_duration: Option<f32>,
+    /// Whether the sender added a `content-warning` tag
+    pub sensitive: bool,
+    /// When the rumor was created
+    pub created_at: Timestamp,
+}
+
+impl FileMessage {
+    /// Decrypts the downloaded attachment bytes.
+    ///
+    /// Uses the associated data rebuilt from the rumor tags, so a file whose
+    /// `ox` or `file-type` tag was altered fails to decrypt. Compressed files
+    /// are decompressed afterwards, up to [`crate::MAX_DECOMPRESSED_SIZE`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The encrypted bytes fetched from `url`.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the plaintext file, or a CryptoError.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let params = self
+            .params
+            .as_ref()
+            .ok_or_else(|| CryptoError::GenericError("Missing decryption parameters".into()))?;
+        let plaintext =
+            crypto::decrypt_data_with_aad(data, params, self.aad.as_deref().unwrap_or_default())?;
+
+        let Some(ref compression) = self.compression else {
+            return Ok(plaintext);
+        };
+        match CompressionAlgorithm::from_tag_value(compression) {
+            Some(CompressionAlgorithm::Deflate) => {
+                crate::media::inflate(&plaintext, crate::MAX_DECOMPRESSED_SIZE)
+                    .map_err(|e| CryptoError::GenericError(format!("Failed to decompress: {e}")))
+            }
+            None => Err(CryptoError::GenericError(format!(
+                "Unsupported compression: {compression}"
+            ))),
+        }
+    }
+}
+
+impl DecodedMessage {
+    /// Decodes an unwrapped gift wrap into a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret_key` - The receiver's secret key, used for NIP-44 wrapped attachment keys.
+    /// * `gift` - The unwrapped gift to decode.
+    ///
+    /// # Returns
+    ///
+    /// The decoded message. Unknown kinds are returned as [`DecodedMessage::Other`].
+    pub fn decode(secret_key: &SecretKey, gift: UnwrappedGift) -> Self {
+        let params = match nip44_params_payload(&gift.rumor.tags) {
+            Some(payload) => {
+                match crypto::decrypt_params_nip44(secret_key, &gift.sender, payload) {
+                    Ok(params) => Some(params),
+                    Err(e) => {
+                        warn!("Failed to decrypt attachment key: {}", e);
+                        None
+                    }
+                }
+            }
+            None => tag_params(&gift.rumor.tags),
+        };
+        Self::from_parts(gift, params)
+    }
+
+    /// Decodes an unwrapped gift wrap, decrypting attachment keys with a signer.
+    ///
+    /// Same as [`DecodedMessage::decode`], but works with remote (NIP-46)
+    /// signers that never expose the secret key.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - The receiver's signer, used for NIP-44 wrapped attachment keys.
+    /// * `gift` - The unwrapped gift to decode.
+    ///
+    /// # Returns
+    ///
+    /// The decoded message. Unknown kinds are returned as [`DecodedMessage::Other`].
+    pub async fn decode_with_signer<T>(signer: &T, gift: UnwrappedGift) -> Self
+    where
+        T: NostrSigner,
+    {
+        let params = match nip44_params_payload(&gift.rumor.tags) {
+            Some(payload) => {
+                let decrypted = signer
+                    .nip44_decrypt(&gift.sender, payload)
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()));
+                match decrypted {
+                    Ok(params) => Some(params),
+                    Err(e) => {
+                        warn!("Failed to decrypt attachment key: {}", e);
+                        None
+                    }
+                }
+            }
+            None => tag_params(&gift.rumor.tags),
+        };
+        Self::from_parts(gift, params)
+    }
+
+    /// Builds the message from the gift and its recovered attachment key.
+    fn from_parts(gift: UnwrappedGift, params: Option<EncryptionParams>) -> Self {
+        let UnwrappedGift { sender, mut rumor } = gift;
+        let event_id = rumor.id();
+
+        match rumor.kind {
+            Kind::PrivateDirectMessage => DecodedMessage::Text(TextMessage {
+                sender,
+                event_id,
+                content: rumor.content,
+                created_at: rumor.created_at,
+            }),
+            kind if kind == Kind::from_u16(15) => {
+                let aad = file_aad(&rumor.tags);
+                let dimensions = tag_value(&rumor.tags, "dim").and_then(|dim| {
+                    let (w, h) = dim.split_once('x')?;
+                    Some((w.parse().ok()?, h.parse().ok()?))
+                });
+
+                DecodedMessage::File(FileMessage {
+                    sender,
+                    event_id,
+                    mime_type: tag_value(&rumor.tags, "file-type").map(str::to_string),
+                    size: tag_value(&rumor.tags, "size").and_then(|s| s.parse().ok()),
+                    hash: tag_value(&rumor.tags, "ox").map(str::to_string),
+                    params,
+                    aad,
+                    blurhash: tag_value(&rumor.tags, "blurhash").map(str::to_string),
+                    dimensions,
+                    caption: tag_value(&rumor.tags, "caption").map(str::to_string),
+                    compression: tag_value(&rumor.tags, "compression").map(str::to_string),
+                    voice_duration: tag_value(&rumor.tags, "voice").and_then(|d| d.parse().ok()),
+                    sensitive: crate::is_sensitive(&rumor),
+                    created_at: rumor.created_at,
+                    url: rumor.content,
+                })
+            }
+            _ => DecodedMessage::Other(UnwrappedGift { sender, rumor }),
+        }
+    }
+
+    /// Returns the public key of the sender.
+    pub fn sender(&self) -> PublicKey {
+        match self {
+            DecodedMessage::Text(msg) => msg.sender,
+            DecodedMessage::File(file) => file.sender,
+            DecodedMessage::Other(gift) => gift.sender,
+        }
+    }
+
+    /// Returns the id of the rumor, if known.
+    pub fn event_id(&self) -> Option<EventId> {
+        match self {
+            DecodedMessage::Text(msg) => Some(msg.event_id),
+            DecodedMessage::File(file) => Some(file.event_id),
+            DecodedMessage::Other(gift) => gift.rumor.id,
+        }
+    }
+}
+
+/// Returns the first value of the tag named `name`.
+pub(crate) fn tag_value<'a>(tags: &'a Tags, name: &str) -> Option<&'a str> {
+    tags.iter()
+        .find(|tag| tag.as_slice().first().map(String::as_str) == Some(name))
+        .and_then(|tag| tag.content())
+}
+
+/// Rebuilds the associated data from the rumor's own tags, if the sender bound any.
+fn file_aad(tags: &Tags) -> Option<Vec<u8>> {
+    if tag_value(tags, crypto::ATTACHMENT_AAD_TAG)? != crypto::ATTACHMENT_AAD_VALUE {
+        warn!("Unknown attachment AAD scheme");
+    }
+    Some(crypto::attachment_aad(
+        tag_value(tags, "ox").unwrap_or_default(),
+        tag_value(tags, "file-type").unwrap_or_default(),
+    ))
+}
+
+/// Returns the NIP-44 encrypted attachment key, if the sender used that scheme.
+fn nip44_params_payload(tags: &Tags) -> Option<&str> {
+    if tag_value(tags, "key-scheme") != Some(AttachmentKeyScheme::Nip44.tag_value()) {
+        return None;
+    }
+    tag_value(tags, "decryption-params")
+}
+
+/// Reads the attachment key and nonce from plaintext tags.
+fn tag_params(tags: &Tags) -> Option<EncryptionParams> {
+    Some(EncryptionParams {
+        key: tag_value(tags, "decryption-key")?.to_string(),
+        nonce: tag_value(tags, "decryption-nonce")?.to_string(),
+        tag_size: crypto::DEFAULT_TAG_SIZE,
+    })
+}
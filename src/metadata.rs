@@ -27,7 +27,7 @@ impl std::error::Error for MetadataError {}
 ///
 /// This struct contains all the fields that can be configured in user metadata.
 /// It provides a flexible way to build metadata with optional fields.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct MetadataConfig {
     /// The name of the user.
     pub name: String,
@@ -225,6 +225,12 @@ impl MetadataConfigBuilder {
     }
 }
 
+impl Default for MetadataConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Creates and configures metadata for a vector user.
 ///
 /// This function builds a Metadata object with the provided information.
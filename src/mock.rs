@@ -0,0 +1,150 @@
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use log::debug;
+use nostr_sdk::nips::nip96::ServerConfig;
+use nostr_sdk::Url;
+use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// Shared switches controlling how the mock server answers
+#[derive(Debug, Default)]
+struct MockState {
+    requests: AtomicUsize,
+    failures_remaining: AtomicUsize,
+    stall: AtomicBool,
+}
+
+/// A mock NIP-96 upload server running on the local tokio runtime
+///
+/// Binds to a random loopback port and answers `POST /upload` with a NIP-96
+/// success response. It can be told to fail or stall so retry and stall
+/// handling can be tested without a live server. The server stops when this
+/// value is dropped.
+#[derive(Debug)]
+pub struct MockNip96Server {
+    addr: SocketAddr,
+    state: Arc<MockState>,
+    task: JoinHandle<()>,
+}
+
+impl MockNip96Server {
+    /// Starts a mock server on a random loopback port
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the running server, or an I/O error if binding failed.
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(MockState::default());
+
+        let task_state = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = task_state.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |req| handle(req, addr, state.clone()));
+                    if let Err(e) = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await
+                    {
+                        debug!("Mock NIP-96 connection closed: {e}");
+                    }
+                });
+            }
+        });
+
+        Ok(Self { addr, state, task })
+    }
+
+    /// Returns the NIP-96 server configuration pointing at this server
+    pub fn server_config(&self) -> ServerConfig {
+        let base = self.base_url();
+        ServerConfig {
+            api_url: base.join("/upload").expect("valid mock upload URL"),
+            download_url: base,
+            delegated_to_url: None,
+            content_types: None,
+        }
+    }
+
+    /// Returns the base URL of this server
+    pub fn base_url(&self) -> Url {
+        Url::parse(&format!("http://{}", self.addr)).expect("valid mock base URL")
+    }
+
+    /// Answers the next `count` uploads with `500 Internal Server Error`
+    pub fn fail_next(&self, count: usize) {
+        self.state.failures_remaining.store(count, Ordering::SeqCst);
+    }
+
+    /// Controls whether uploads stall by never reading the request body
+    pub fn set_stall(&self, stall: bool) {
+        self.state.stall.store(stall, Ordering::SeqCst);
+    }
+
+    /// Returns how many upload requests the server has received
+    pub fn request_count(&self) -> usize {
+        self.state.requests.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for MockNip96Server {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Handles one HTTP request to the mock server
+async fn handle(
+    req: Request<Incoming>,
+    addr: SocketAddr,
+    state: Arc<MockState>,
+) -> Result<Response<Full<Bytes>>, hyper::Error> {
+    if req.method() != Method::POST || req.uri().path() != "/upload" {
+        return Ok(respond(StatusCode::NOT_FOUND, String::new()));
+    }
+    state.requests.fetch_add(1, Ordering::SeqCst);
+
+    let should_fail = state
+        .failures_remaining
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok();
+    if should_fail {
+        return Ok(respond(StatusCode::INTERNAL_SERVER_ERROR, String::new()));
+    }
+
+    if state.stall.load(Ordering::SeqCst) {
+        // Hold the request open without reading, so the client's body backs up
+        std::future::pending::<()>().await;
+    }
+
+    let body = req.into_body().collect().await?.to_bytes();
+    let hash = hex::encode(Sha256::digest(&body));
+    let response = serde_json::json!({
+        "status": "success",
+        "message": "Upload successful",
+        "nip94_event": {
+            "tags": [
+                ["url", format!("http://{addr}/{hash}")],
+            ],
+        },
+    });
+
+    Ok(respond(StatusCode::OK, response.to_string()))
+}
+
+/// Builds a response with the given status and body
+fn respond(status: StatusCode, body: String) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response
+}
@@ -0,0 +1,200 @@
+use futures_util::{SinkExt, StreamExt};
+use log::debug;
+use nostr_sdk::prelude::*;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+/// How many published events a slow connection may lag behind before missing some
+const BROADCAST_CAPACITY: usize = 1024;
+
+/// Events shared between the relay's connections
+#[derive(Debug)]
+struct RelayState {
+    events: Mutex<Vec<Event>>,
+    published: broadcast::Sender<Event>,
+    reject: AtomicBool,
+    silent: AtomicBool,
+}
+
+/// An in-process Nostr relay running on the local tokio runtime
+///
+/// Binds to a random loopback port and speaks just enough NIP-01 for the
+/// SDK: `EVENT` is stored and acknowledged, `REQ` replays matching stored
+/// events followed by `EOSE` and then streams new matches, and `CLOSE` ends
+/// a subscription. Every published event is recorded so tests can inspect
+/// what the bot actually sent. The relay stops when this value is dropped.
+#[derive(Debug)]
+pub struct MockRelay {
+    addr: SocketAddr,
+    state: Arc<RelayState>,
+    task: JoinHandle<()>,
+}
+
+impl MockRelay {
+    /// Starts a mock relay on a random loopback port
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the running relay, or an I/O error if binding failed.
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(RelayState {
+            events: Mutex::new(Vec::new()),
+            published: broadcast::channel(BROADCAST_CAPACITY).0,
+            reject: AtomicBool::new(false),
+            silent: AtomicBool::new(false),
+        });
+
+        let task_state = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_connection(stream, task_state.clone()));
+            }
+        });
+
+        Ok(Self { addr, state, task })
+    }
+
+    /// Returns the websocket URL of this relay
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// Makes the relay refuse (or accept again) every published event
+    ///
+    /// Refused events are answered with a negative `OK` and not stored.
+    pub fn set_reject_events(&self, reject: bool) {
+        self.state.reject.store(reject, Ordering::SeqCst);
+    }
+
+    /// Makes the relay ignore (or answer again) every `REQ`
+    ///
+    /// A silent relay stays connected but never sends stored events or
+    /// `EOSE`, like a relay that has stalled.
+    pub fn set_silent(&self, silent: bool) {
+        self.state.silent.store(silent, Ordering::SeqCst);
+    }
+
+    /// Returns every event published to this relay, oldest first
+    pub fn published_events(&self) -> Vec<Event> {
+        self.state.events.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockRelay {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Serves one websocket client until it disconnects
+async fn serve_connection(stream: TcpStream, state: Arc<RelayState>) {
+    let mut ws = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            debug!("Mock relay handshake failed: {e}");
+            return;
+        }
+    };
+    let mut published = state.published.subscribe();
+    let mut subscriptions: HashMap<SubscriptionId, Filter> = HashMap::new();
+
+    loop {
+        let replies = tokio::select! {
+            incoming = ws.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    handle_client_message(text.as_str(), &state, &mut subscriptions)
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => {
+                    debug!("Mock relay connection closed: {e}");
+                    return;
+                }
+                None => return,
+            },
+            event = published.recv() => match event {
+                Ok(event) => subscriptions
+                    .iter()
+                    .filter(|(_, filter)| filter.match_event(&event))
+                    .map(|(id, _)| RelayMessage::event(id.clone(), event.clone()))
+                    .collect(),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+        };
+
+        for reply in replies {
+            if ws.send(Message::text(reply.as_json())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Applies one client message and returns the relay's replies
+fn handle_client_message(
+    text: &str,
+    state: &RelayState,
+    subscriptions: &mut HashMap<SubscriptionId, Filter>,
+) -> Vec<RelayMessage<'static>> {
+    let message = match ClientMessage::from_json(text) {
+        Ok(message) => message,
+        Err(e) => return vec![RelayMessage::notice(format!("invalid message: {e}"))],
+    };
+
+    match message {
+        ClientMessage::Event(event) => {
+            let event = event.into_owned();
+            if let Err(e) = event.verify() {
+                return vec![RelayMessage::ok(event.id, false, format!("invalid: {e}"))];
+            }
+            if state.reject.load(Ordering::SeqCst) {
+                return vec![RelayMessage::ok(event.id, false, "blocked: rejecting events")];
+            }
+            let id = event.id;
+            state.events.lock().unwrap().push(event.clone());
+            let _ = state.published.send(event);
+            vec![RelayMessage::ok(id, true, "")]
+        }
+        ClientMessage::Req {
+            subscription_id,
+            filter,
+        } => {
+            let subscription_id = subscription_id.into_owned();
+            let filter = filter.into_owned();
+            if state.silent.load(Ordering::SeqCst) {
+                return Vec::new();
+            }
+            // Like real relays, answer newest first and honor the filter's limit
+            let mut matching: Vec<Event> = state
+                .events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| filter.match_event(event))
+                .cloned()
+                .collect();
+            matching.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+            matching.truncate(filter.limit.unwrap_or(usize::MAX));
+            let mut replies: Vec<RelayMessage> = matching
+                .into_iter()
+                .map(|event| RelayMessage::event(subscription_id.clone(), event))
+                .collect();
+            replies.push(RelayMessage::eose(subscription_id.clone()));
+            subscriptions.insert(subscription_id, filter);
+            replies
+        }
+        ClientMessage::Close(subscription_id) => {
+            subscriptions.remove(&subscription_id);
+            Vec::new()
+        }
+        _ => vec![RelayMessage::notice("unsupported message")],
+    }
+}
@@ -0,0 +1,232 @@
+use crate::hints::RelayHints;
+use crate::ratelimit::RateLimiter;
+use log::{debug, warn};
+use nostr_sdk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+/// How often the retry worker checks for sends that are due
+const RETRY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Delay before the first retry of a failed send
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+/// Upper bound for the delay between retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(300);
+
+/// A send that no relay has accepted yet
+///
+/// Only the signed gift wrap is kept, so nothing readable reaches the disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingSend {
+    gift_wrap: Event,
+    /// The rumor's NIP-40 expiration, after which the send is dropped
+    expiration: Option<Timestamp>,
+    relays: Vec<String>,
+    #[serde(skip)]
+    id: u64,
+    #[serde(skip)]
+    attempts: u32,
+}
+
+#[derive(Debug)]
+struct OutboxState {
+    pending: VecDeque<(PendingSend, Instant)>,
+    next_id: u64,
+    path: Option<PathBuf>,
+}
+
+/// A queue of sends waiting to be retried after every relay rejected them.
+///
+/// Gift wraps that fail because of a momentary disconnect are queued here and
+/// re-sent with exponential backoff until at least one relay accepts them.
+/// When a path is configured, the queue is written to that file so pending
+/// messages also survive restarts. The file holds the signed, encrypted gift
+/// wraps only. Clones share the same queue.
+#[derive(Debug, Clone)]
+pub struct Outbox {
+    state: Arc<Mutex<OutboxState>>,
+}
+
+impl Outbox {
+    /// Creates a new in-memory Outbox.
+    ///
+    /// # Returns
+    ///
+    /// An empty Outbox.
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(OutboxState {
+                pending: VecDeque::new(),
+                next_id: 0,
+                path: None,
+            })),
+        }
+    }
+
+    /// Creates an Outbox backed by a file on disk.
+    ///
+    /// Sends left over from a previous run are loaded and retried right away.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to load from and write to.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the loaded Outbox.
+    pub fn with_persistence<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let outbox = Self::new();
+        let path = path.as_ref().to_path_buf();
+
+        {
+            let mut state = outbox.state.lock().unwrap();
+            if path.exists() {
+                let reader = BufReader::new(File::open(&path)?);
+                for line in reader.lines() {
+                    let line = line?;
+                    match serde_json::from_str::<PendingSend>(&line) {
+                        Ok(mut send) => {
+                            send.id = state.next_id;
+                            state.next_id += 1;
+                            state.pending.push_back((send, Instant::now()));
+                        }
+                        Err(e) => warn!("Skipping unreadable outbox entry: {e}"),
+                    }
+                }
+            }
+            state.path = Some(path);
+        }
+
+        Ok(outbox)
+    }
+
+    /// Returns the number of sends waiting to be delivered.
+    pub fn pending_count(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    /// Queues a failed send for retry.
+    pub(crate) fn push(
+        &self,
+        gift_wrap: Event,
+        expiration: Option<Timestamp>,
+        relays: Vec<String>,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        let send = PendingSend {
+            gift_wrap,
+            expiration,
+            relays,
+            id: state.next_id,
+            attempts: 0,
+        };
+        state.next_id += 1;
+        state
+            .pending
+            .push_back((send, Instant::now() + RETRY_BASE_DELAY));
+        state.persist();
+    }
+
+    /// Starts the background task that retries queued sends.
+    ///
+    /// The task stops once every clone of the Outbox has been dropped.
+    pub(crate) fn spawn_worker(
+        &self,
+        client: Client,
+        hints: RelayHints,
+        rate_limiter: Option<RateLimiter>,
+    ) {
+        let state = Arc::downgrade(&self.state);
+        tokio::spawn(retry_loop(state, client, hints, rate_limiter));
+    }
+}
+
+impl Default for Outbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutboxState {
+    /// Rewrites the backing file, if any, with the current queue.
+    fn persist(&self) {
+        let Some(ref path) = self.path else {
+            return;
+        };
+
+        let written = File::create(path).and_then(|mut file| {
+            for (send, _) in &self.pending {
+                let line = serde_json::to_string(send).map_err(std::io::Error::other)?;
+                writeln!(file, "{line}")?;
+            }
+            Ok(())
+        });
+        if let Err(e) = written {
+            warn!("Failed to persist outbox: {e}");
+        }
+    }
+}
+
+/// Computes the delay before the next retry of a send.
+fn retry_delay(attempts: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(1u32 << attempts.min(16))
+        .min(RETRY_MAX_DELAY)
+}
+
+/// Retries due sends until the outbox is dropped.
+async fn retry_loop(
+    state: Weak<Mutex<OutboxState>>,
+    client: Client,
+    hints: RelayHints,
+    rate_limiter: Option<RateLimiter>,
+) {
+    loop {
+        tokio::time::sleep(RETRY_POLL_INTERVAL).await;
+
+        let Some(state) = state.upgrade() else {
+            debug!("Outbox dropped, stopping retry worker");
+            return;
+        };
+
+        let now = Instant::now();
+        let due: Vec<PendingSend> = state
+            .lock()
+            .unwrap()
+            .pending
+            .iter()
+            .filter(|(_, next_attempt)| *next_attempt <= now)
+            .map(|(send, _)| send.clone())
+            .collect();
+
+        for send in due {
+            // Typing indicators and other expiring rumors are not worth delivering late
+            let expired = send.expiration.is_some_and(|exp| exp <= Timestamp::now());
+            let delivered = !expired && {
+                if let Some(ref rate_limiter) = rate_limiter {
+                    rate_limiter.acquire().await;
+                }
+                crate::deliver_gift_wrap(&client, &hints, &send.gift_wrap, &send.relays)
+                    .await
+                    .is_ok_and(|result| result.is_accepted())
+            };
+
+            let mut guard = state.lock().unwrap();
+            if delivered || expired {
+                debug!("Outbox send {} finished (expired: {expired})", send.id);
+                guard.pending.retain(|(pending, _)| pending.id != send.id);
+            } else if let Some(entry) = guard.pending.iter_mut().find(|(p, _)| p.id == send.id) {
+                entry.0.attempts += 1;
+                entry.1 = Instant::now() + retry_delay(entry.0.attempts);
+                debug!("Outbox send {} failed, attempt {}", send.id, entry.0.attempts);
+            }
+            guard.persist();
+        }
+    }
+}
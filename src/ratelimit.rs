@@ -0,0 +1,90 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Rate used by [`crate::VectorBotBuilder::rate_limit`] when given an invalid one
+pub const FALLBACK_PER_SEC: f64 = 1.0;
+
+/// Errors that can occur when configuring a rate limit
+#[derive(Error, Debug)]
+pub enum RateLimitError {
+    /// The rate is zero, negative, NaN or infinite
+    #[error("Invalid rate {0}: sends per second must be positive and finite")]
+    InvalidRate(f64),
+}
+
+/// A token bucket limiting how many events a bot publishes per second.
+///
+/// Clones share the same bucket, so every clone of a bot draws from one budget.
+/// When the bucket is empty, [`RateLimiter::acquire`] waits for a token instead
+/// of failing.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    state: Arc<Mutex<BucketState>>,
+    per_sec: f64,
+    capacity: f64,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a new RateLimiter.
+    ///
+    /// # Arguments
+    ///
+    /// * `per_sec` - Sustained number of sends allowed per second. Values below
+    ///   one allow a send every `1 / per_sec` seconds.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a new RateLimiter with a full bucket, or
+    /// [`RateLimitError::InvalidRate`] if `per_sec` is not a positive, finite
+    /// number.
+    pub fn new(per_sec: f64) -> Result<Self, RateLimitError> {
+        if !(per_sec.is_finite() && per_sec > 0.0) {
+            return Err(RateLimitError::InvalidRate(per_sec));
+        }
+        // Allow a burst of up to one second's worth of sends (at least one)
+        let capacity = per_sec.max(1.0);
+
+        Ok(Self {
+            state: Arc::new(Mutex::new(BucketState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+            })),
+            per_sec,
+            capacity,
+        })
+    }
+
+    /// Returns the configured sends per second.
+    pub fn per_sec(&self) -> f64 {
+        self.per_sec
+    }
+
+    /// Waits until a token is available and consumes it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+                state.tokens = (state.tokens + elapsed * self.per_sec).min(self.capacity);
+                state.last_refill = now;
+
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                }
+
+                Duration::from_secs_f64((1.0 - state.tokens) / self.per_sec)
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::client::subscribe_gift_wraps;
+use log::{debug, info, warn};
+use nostr_sdk::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+/// How often the supervisor checks relay status while connected
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Delay before the first reconnection attempt
+const RECONNECT_BASE_DELAY: Duration = Duration::from_secs(2);
+
+/// Upper bound for the delay between reconnection attempts
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(300);
+
+/// How long a single reconnection attempt waits for relays to connect
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Background task that reconnects the client when every relay drops.
+///
+/// The task is started by [`ReconnectSupervisor::start`] and stops when
+/// [`ReconnectSupervisor::stop`] is called or every clone is dropped.
+#[derive(Debug, Clone)]
+pub(crate) struct ReconnectSupervisor {
+    token: CancellationToken,
+    started: Arc<AtomicBool>,
+    _guard: Arc<DropGuard>,
+}
+
+impl ReconnectSupervisor {
+    /// Creates a supervisor that is not running yet.
+    pub(crate) fn new() -> Self {
+        let token = CancellationToken::new();
+        Self {
+            _guard: Arc::new(token.clone().drop_guard()),
+            token,
+            started: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Starts monitoring the client. Later calls do nothing.
+    pub(crate) fn start(&self, client: Client, public_key: PublicKey) {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        tokio::spawn(supervise(self.token.clone(), client, public_key));
+    }
+
+    /// Stops the supervisor, e.g. before a deliberate disconnect.
+    pub(crate) fn stop(&self) {
+        self.token.cancel();
+    }
+}
+
+/// Computes the delay before the next reconnection attempt.
+fn reconnect_delay(attempts: u32) -> Duration {
+    RECONNECT_BASE_DELAY
+        .saturating_mul(1u32 << attempts.min(16))
+        .min(RECONNECT_MAX_DELAY)
+}
+
+/// Watches relay status and reconnects on total disconnect until cancelled.
+async fn supervise(token: CancellationToken, client: Client, public_key: PublicKey) {
+    let mut attempts: u32 = 0;
+
+    loop {
+        let delay = match attempts {
+            0 => STATUS_POLL_INTERVAL,
+            n => reconnect_delay(n - 1),
+        };
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = tokio::time::sleep(delay) => {}
+        }
+
+        let relays = client.relays().await;
+        if relays.is_empty() || relays.values().any(Relay::is_connected) {
+            attempts = 0;
+            continue;
+        }
+
+        if attempts == 0 {
+            warn!("All relays disconnected, reconnecting");
+        }
+        let output = client.try_connect(CONNECT_TIMEOUT).await;
+        if output.success.is_empty() {
+            attempts = attempts.saturating_add(1);
+            debug!("Reconnection attempt {attempts} failed");
+            continue;
+        }
+
+        match subscribe_gift_wraps(&client, public_key).await {
+            Ok(()) => info!("Reconnected to {} relays", output.success.len()),
+            Err(e) => warn!("Reconnected, but failed to resubscribe: {e}"),
+        }
+        attempts = 0;
+    }
+
+    debug!("Reconnection supervisor stopped");
+}
@@ -0,0 +1,172 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// Store holding the ids of events the bot has already handled.
+pub const SEEN_EVENTS_STORE: &str = "seen_events";
+
+/// Store holding the time of the last message with each counterparty.
+pub const CONVERSATIONS_STORE: &str = "conversations";
+
+/// Store holding the ids of received messages not yet marked read, per counterparty.
+pub const UNREAD_STORE: &str = "unread";
+
+/// Errors that can occur while reading or writing a [`Storage`] backend
+#[derive(Debug, Error)]
+pub enum StorageError {
+    /// Error reported by the SQLite backend
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    /// Generic error with message
+    #[error("{0}")]
+    GenericError(String),
+}
+
+/// A key-value backend for the bot's persistent caches.
+///
+/// Values are grouped into named stores (such as [`SEEN_EVENTS_STORE`]) so
+/// several caches can share one backend. Implement this trait to keep the
+/// bot's state in your own database.
+pub trait Storage: Debug + Send + Sync {
+    /// Returns the value stored under `key`, if any.
+    fn get(&self, store: &str, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Stores `value` under `key`, replacing any previous value.
+    fn set(&self, store: &str, key: &str, value: &[u8]) -> Result<(), StorageError>;
+
+    /// Returns every key and value in a store.
+    fn list(&self, store: &str) -> Result<Vec<(String, Vec<u8>)>, StorageError>;
+
+    /// Removes `key` from a store. Removing a missing key is not an error.
+    fn remove(&self, store: &str, key: &str) -> Result<(), StorageError>;
+}
+
+/// A [`Storage`] backend that keeps everything in memory.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    stores: Mutex<HashMap<String, BTreeMap<String, Vec<u8>>>>,
+}
+
+impl MemoryStorage {
+    /// Creates an empty MemoryStorage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, store: &str, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        let stores = self.stores.lock().unwrap();
+        Ok(stores.get(store).and_then(|entries| entries.get(key)).cloned())
+    }
+
+    fn set(&self, store: &str, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        let mut stores = self.stores.lock().unwrap();
+        stores
+            .entry(store.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn list(&self, store: &str) -> Result<Vec<(String, Vec<u8>)>, StorageError> {
+        let stores = self.stores.lock().unwrap();
+        Ok(stores
+            .get(store)
+            .map(|entries| entries.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default())
+    }
+
+    fn remove(&self, store: &str, key: &str) -> Result<(), StorageError> {
+        let mut stores = self.stores.lock().unwrap();
+        if let Some(entries) = stores.get_mut(store) {
+            entries.remove(key);
+        }
+        Ok(())
+    }
+}
+
+/// A [`Storage`] backend persisted in a SQLite database.
+///
+/// All stores share a single `kv` table keyed by store name and key.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStorage {
+    /// Opens (or creates) a SQLite database at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The database file.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the storage, or a StorageError.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, StorageError> {
+        Self::from_connection(rusqlite::Connection::open(path)?)
+    }
+
+    /// Opens a SQLite database that lives only in memory.
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        Self::from_connection(rusqlite::Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: rusqlite::Connection) -> Result<Self, StorageError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS kv (
+                store TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value BLOB NOT NULL,
+                PRIMARY KEY (store, key)
+            )",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Storage for SqliteStorage {
+    fn get(&self, store: &str, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        use rusqlite::OptionalExtension;
+
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT value FROM kv WHERE store = ?1 AND key = ?2",
+                (store, key),
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn set(&self, store: &str, key: &str, value: &[u8]) -> Result<(), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO kv (store, key, value) VALUES (?1, ?2, ?3)",
+            (store, key, value),
+        )?;
+        Ok(())
+    }
+
+    fn list(&self, store: &str) -> Result<Vec<(String, Vec<u8>)>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT key, value FROM kv WHERE store = ?1 ORDER BY key")?;
+        let rows = stmt.query_map([store], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn remove(&self, store: &str, key: &str) -> Result<(), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM kv WHERE store = ?1 AND key = ?2", (store, key))?;
+        Ok(())
+    }
+}
@@ -7,6 +7,8 @@ use std::fmt;
 pub enum SubscriptionError {
     /// Invalid filter configuration
     InvalidFilter(String),
+    /// The configuration has no public key to filter for
+    MissingPubkey,
 }
 
 impl fmt::Display for SubscriptionError {
@@ -15,6 +17,9 @@ impl fmt::Display for SubscriptionError {
             SubscriptionError::InvalidFilter(msg) => {
                 write!(f, "Invalid filter configuration: {msg}")
             }
+            SubscriptionError::MissingPubkey => {
+                write!(f, "Subscription configuration has no public key")
+            }
         }
     }
 }
@@ -24,8 +29,8 @@ impl std::error::Error for SubscriptionError {}
 /// Configuration options for subscriptions
 #[derive(Debug, Clone)]
 pub struct SubscriptionConfig {
-    /// The public key to filter events for
-    pub pubkey: PublicKey,
+    /// The public key to filter events for, if one has been chosen
+    pub pubkey: Option<PublicKey>,
     /// The kind of events to filter
     pub kind: Kind,
     /// The maximum number of events to return (0 means no limit)
@@ -35,13 +40,38 @@ pub struct SubscriptionConfig {
 impl Default for SubscriptionConfig {
     fn default() -> Self {
         Self {
-            pubkey: PublicKey::from_bech32("npub1").expect("Invalid public key"),
+            pubkey: None,
             kind: Kind::GiftWrap,
             limit: 0,
         }
     }
 }
 
+impl SubscriptionConfig {
+    /// Creates a configuration for gift wraps addressed to `pubkey`.
+    pub fn for_pubkey(pubkey: PublicKey) -> Self {
+        Self {
+            pubkey: Some(pubkey),
+            ..Self::default()
+        }
+    }
+
+    /// Builds the subscription filter described by this configuration.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the filter, or [`SubscriptionError::MissingPubkey`]
+    /// if no public key was set.
+    ///
+    /// # Errors
+    ///
+    /// Also fails like [`create_gift_wrap_subscription`] when the limit is too high.
+    pub fn to_filter(&self) -> Result<Filter, SubscriptionError> {
+        let pubkey = self.pubkey.ok_or(SubscriptionError::MissingPubkey)?;
+        create_gift_wrap_subscription(pubkey, Some(self.kind), Some(self.limit))
+    }
+}
+
 /// Creates a subscription filter for gift wrap events.
 ///
 /// This function sets up a filter to subscribe to gift wrap events for a specific public key.
@@ -78,3 +108,20 @@ pub fn create_gift_wrap_subscription(
         .kind(kind)
         .limit(limit.try_into().unwrap()))
 }
+
+/// Creates a filter for NIP-17 DM relay lists (kind 10050).
+///
+/// Used to discover which relays a recipient reads gift wraps from.
+///
+/// # Arguments
+///
+/// * `pubkeys` - The authors whose DM relay lists should be fetched.
+///
+/// # Returns
+///
+/// A Filter matching the kind 10050 events of the given authors.
+pub fn create_dm_relay_list_subscription(pubkeys: &[PublicKey]) -> Filter {
+    Filter::new()
+        .authors(pubkeys.iter().copied())
+        .kind(Kind::InboxRelays)
+}
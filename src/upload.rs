@@ -3,17 +3,21 @@ use nostr_sdk::hashes::{sha256::Hash as Sha256Hash, Hash};
 use nostr_sdk::{
     nips::nip96::{ServerConfig, UploadResponse, UploadResponseStatus},
     nips::nip98::{HttpData, HttpMethod},
-    NostrSigner, TagKind, TagStandard, Url,
+    NostrSigner, TagKind, TagStandard, Tags, Url,
 };
 use reqwest::{
     multipart::{self, Part},
     Body, Client,
 };
+use rand::Rng;
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 use tokio::sync::mpsc;
 
+/// User-Agent sent by the SDK's HTTP clients unless configured otherwise
+pub const DEFAULT_USER_AGENT: &str = concat!("vector-sdk/", env!("CARGO_PKG_VERSION"));
+
 /// Configuration options for the upload client
 #[derive(Debug, Clone)]
 pub struct UploadConfig {
@@ -23,8 +27,10 @@ pub struct UploadConfig {
     pub pool_idle_timeout: std::time::Duration,
     /// Maximum idle connections per host
     pub pool_max_idle_per_host: usize,
-    /// Stall detection threshold (in milliseconds)
-    pub stall_threshold: u32,
+    /// How long an upload may make no progress before it is aborted
+    pub stall_timeout: std::time::Duration,
+    /// User-Agent header sent with every request
+    pub user_agent: String,
 }
 
 impl Default for UploadConfig {
@@ -33,7 +39,8 @@ impl Default for UploadConfig {
             connect_timeout: std::time::Duration::from_secs(5),
             pool_idle_timeout: std::time::Duration::from_secs(90),
             pool_max_idle_per_host: 2,
-            stall_threshold: 200, // 20 seconds (200 * 100ms)
+            stall_timeout: std::time::Duration::from_secs(20),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
         }
     }
 }
@@ -83,7 +90,8 @@ pub fn make_client(
         let mut builder = Client::builder()
             .connect_timeout(config.connect_timeout)
             .pool_idle_timeout(config.pool_idle_timeout)
-            .pool_max_idle_per_host(config.pool_max_idle_per_host);
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .user_agent(config.user_agent);
 
         if let Some(proxy) = proxy {
             let proxy = format!("socks5h://{proxy}");
@@ -117,7 +125,28 @@ impl ProgressTrackingStream {
     ///
     /// A new ProgressTrackingStream
     pub fn new(data: Vec<u8>, bytes_sent: Arc<Mutex<u64>>, chunk_size: usize) -> Self {
-        let (tx, rx) = mpsc::channel(8); // Buffer size of 8 chunks
+        Self::with_buffer_depth(data, bytes_sent, chunk_size, 8)
+    }
+
+    /// Creates a new ProgressTrackingStream with a custom channel buffer depth
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data to be sent through the stream
+    /// * `bytes_sent` - Counter for tracking bytes sent
+    /// * `chunk_size` - Size of each chunk to send
+    /// * `buffer_depth` - Number of chunks buffered ahead of the consumer (minimum 1)
+    ///
+    /// # Returns
+    ///
+    /// A new ProgressTrackingStream
+    pub fn with_buffer_depth(
+        data: Vec<u8>,
+        bytes_sent: Arc<Mutex<u64>>,
+        chunk_size: usize,
+        buffer_depth: usize,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(buffer_depth.max(1));
 
         // Spawn a background task to feed the stream
         tokio::spawn(async move {
@@ -169,6 +198,30 @@ impl futures_util::Stream for ProgressTrackingStream {
     }
 }
 
+/// Result of a successful upload, including the server's NIP-94 metadata
+#[derive(Debug, Clone)]
+pub struct UploadResult {
+    /// The URL of the uploaded file
+    pub url: Url,
+    /// The full NIP-94 tag set returned by the server (e.g. `ox`, `x`, `size`, `dim`)
+    pub nip94_tags: Tags,
+}
+
+impl UploadResult {
+    /// Returns the first value of the NIP-94 tag with the given name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The tag name, e.g. `"ox"` or `"size"`
+    ///
+    /// # Returns
+    ///
+    /// The tag value, or None if the server did not provide it.
+    pub fn tag_value(&self, name: &str) -> Option<&str> {
+        crate::message::tag_value(&self.nip94_tags, name)
+    }
+}
+
 /// Progress callback function type
 ///
 /// A boxed function that takes an optional percentage and bytes sent,
@@ -176,15 +229,39 @@ impl futures_util::Stream for ProgressTrackingStream {
 pub type ProgressCallback =
     Box<dyn Fn(Option<u8>, Option<u64>) -> Result<(), String> + Send + Sync>;
 
+/// Strategy used to space out upload retry attempts
+#[derive(Debug, Clone, Default)]
+pub enum RetryStrategy {
+    /// Wait `retry_spacing` between every attempt
+    #[default]
+    Fixed,
+    /// Double the delay on each attempt, capped at `max`, with random jitter
+    ///
+    /// The actual sleep is drawn uniformly from the upper half of the backoff
+    /// window, so many clients failing at once do not retry in lockstep.
+    ExponentialJitter {
+        /// Delay before the first retry
+        base: std::time::Duration,
+        /// Upper bound for any single delay
+        max: std::time::Duration,
+    },
+}
+
 /// Upload configuration with retry settings
 #[derive(Debug, Clone)]
 pub struct UploadParams {
     /// Number of retry attempts
     pub retry_count: u32,
-    /// Delay between retry attempts
+    /// Delay between retry attempts (used by [`RetryStrategy::Fixed`])
     pub retry_spacing: std::time::Duration,
+    /// How the delay between attempts is computed
+    pub retry_strategy: RetryStrategy,
     /// Chunk size for streaming
     pub chunk_size: usize,
+    /// Number of chunks buffered ahead of the HTTP body
+    pub buffer_depth: usize,
+    /// File name sent in the multipart form (random `<hex>.bin` if None)
+    pub file_name: Option<String>,
 }
 
 impl Default for UploadParams {
@@ -192,15 +269,41 @@ impl Default for UploadParams {
         Self {
             retry_count: 3,
             retry_spacing: std::time::Duration::from_secs(2),
+            retry_strategy: RetryStrategy::Fixed,
             chunk_size: 64 * 1024, // 64 KB
+            buffer_depth: 8,
+            file_name: None,
+        }
+    }
+}
+
+impl UploadParams {
+    /// Computes how long to sleep before the given retry attempt
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The retry attempt number, starting at 1
+    ///
+    /// # Returns
+    ///
+    /// The delay to wait before performing the attempt.
+    pub fn retry_delay(&self, attempt: u32) -> std::time::Duration {
+        match self.retry_strategy {
+            RetryStrategy::Fixed => self.retry_spacing,
+            RetryStrategy::ExponentialJitter { base, max } => {
+                let exponent = attempt.saturating_sub(1).min(31);
+                let backoff = base.saturating_mul(1u32 << exponent).min(max);
+                let half = backoff / 2;
+                let jitter_ms = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+                half + std::time::Duration::from_millis(jitter_ms)
+            }
         }
     }
 }
 
 /// Uploads data to a NIP-96 server with progress callback
 ///
-/// This function extends the standard NIP-96 upload_data function by adding progress reporting
-/// via a callback function that is called periodically during the upload process.
+/// Thin wrapper around [`upload_data_with_progress_full`] that only returns the URL.
 ///
 /// # Arguments
 ///
@@ -216,6 +319,7 @@ impl Default for UploadParams {
 /// # Returns
 ///
 /// A Result containing the URL of the uploaded file or an UploadError.
+#[allow(clippy::too_many_arguments)]
 pub async fn upload_data_with_progress<T>(
     signer: &T,
     desc: &ServerConfig,
@@ -229,6 +333,55 @@ pub async fn upload_data_with_progress<T>(
 where
     T: NostrSigner,
 {
+    upload_data_with_progress_full(
+        signer,
+        desc,
+        file_data,
+        mime_type,
+        proxy,
+        progress_callback,
+        params,
+        config,
+    )
+    .await
+    .map(|result| result.url)
+}
+
+/// Uploads data to a NIP-96 server with progress callback, returning the NIP-94 metadata
+///
+/// This function extends the standard NIP-96 upload_data function by adding progress reporting
+/// via a callback function that is called periodically during the upload process.
+///
+/// # Arguments
+///
+/// * `signer` - The signer for NIP98 authorization
+/// * `desc` - The server configuration
+/// * `file_data` - The file data to upload
+/// * `mime_type` - The MIME type of the file
+/// * `proxy` - Optional proxy address
+/// * `progress_callback` - The progress callback function
+/// * `params` - Optional upload parameters with retry settings
+/// * `config` - Optional upload client configuration
+///
+/// # Returns
+///
+/// A Result containing the URL and NIP-94 tags of the uploaded file or an UploadError.
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_data_with_progress_full<T>(
+    signer: &T,
+    desc: &ServerConfig,
+    file_data: Vec<u8>,
+    mime_type: Option<&str>,
+    proxy: Option<SocketAddr>,
+    progress_callback: ProgressCallback,
+    params: Option<UploadParams>,
+    config: Option<UploadConfig>,
+) -> Result<UploadResult, UploadError>
+where
+    T: NostrSigner,
+{
+    validate_server_config(desc)?;
+
     let params = params.unwrap_or_default();
     let config = config.unwrap_or_default();
 
@@ -239,7 +392,7 @@ where
         if attempt > 0 {
             debug!("Retry attempt {} of {}", attempt, params.retry_count);
             // Sleep before retry
-            tokio::time::sleep(params.retry_spacing).await;
+            tokio::time::sleep(params.retry_delay(attempt)).await;
         }
 
         match upload_attempt(
@@ -250,11 +403,11 @@ where
             proxy,
             &progress_callback,
             &config,
-            params.chunk_size,
+            &params,
         )
         .await
         {
-            Ok(url) => return Ok(url),
+            Ok(result) => return Ok(result),
             Err(e) => {
                 last_error = Some(e);
                 // Continue to next retry attempt
@@ -267,7 +420,81 @@ where
         .unwrap_or_else(|| UploadError::UploadError("No upload attempts were made".to_string())))
 }
 
+/// Checks that a NIP-96 server configuration can be uploaded to
+///
+/// A config with a placeholder `api_url` (anything that is not an absolute
+/// http(s) URL with a host) would otherwise only fail later with a confusing
+/// request error.
+///
+/// # Arguments
+///
+/// * `desc` - The server configuration to check
+///
+/// # Returns
+///
+/// A Result that is an UploadError describing the first unusable field.
+pub fn validate_server_config(desc: &ServerConfig) -> Result<(), UploadError> {
+    let is_http = |url: &Url| matches!(url.scheme(), "http" | "https") && url.has_host();
+
+    if !is_http(&desc.api_url) {
+        let reason = match desc.delegated_to_url {
+            Some(ref delegate) => format!("server delegates uploads to {delegate}"),
+            None => "expected an http(s) URL".to_string(),
+        };
+        return Err(UploadError::GenericError(format!(
+            "Server config has no usable api_url ({}): {reason}",
+            desc.api_url
+        )));
+    }
+    if !is_http(&desc.download_url) {
+        return Err(UploadError::GenericError(format!(
+            "Server config has no usable download_url ({})",
+            desc.download_url
+        )));
+    }
+    Ok(())
+}
+
+/// Builds a NIP-98 `Authorization` header value for an HTTP request
+///
+/// Signs a kind-27235 event describing the URL, method and optional body
+/// hash, and encodes it as `Nostr <base64 event json>`.
+///
+/// # Arguments
+///
+/// * `signer` - The signer for the authorization event
+/// * `url` - The absolute URL being requested
+/// * `method` - The HTTP method of the request
+/// * `payload` - SHA-256 of the request body, if it has one
+///
+/// # Returns
+///
+/// A Result containing the header value or an UploadError.
+pub async fn nip98_auth_header<T>(
+    signer: &T,
+    url: &Url,
+    method: HttpMethod,
+    payload: Option<Sha256Hash>,
+) -> Result<String, UploadError>
+where
+    T: NostrSigner,
+{
+    let mut data = HttpData::new(url.clone(), method);
+    if let Some(payload) = payload {
+        data = data.payload(payload);
+    }
+    data.to_authorization(signer)
+        .await
+        .map_err(|e| UploadError::UploadError(e.to_string()))
+}
+
+/// Generates a random upload file name, so uploads do not share one name
+fn random_file_name() -> String {
+    format!("{}.bin", hex::encode(rand::thread_rng().gen::<[u8; 16]>()))
+}
+
 /// Internal function that performs a single upload attempt
+#[allow(clippy::too_many_arguments)]
 async fn upload_attempt<T>(
     signer: &T,
     desc: &ServerConfig,
@@ -276,18 +503,15 @@ async fn upload_attempt<T>(
     proxy: Option<SocketAddr>,
     progress_callback: &ProgressCallback,
     config: &UploadConfig,
-    chunk_size: usize,
-) -> Result<Url, UploadError>
+    params: &UploadParams,
+) -> Result<UploadResult, UploadError>
 where
     T: NostrSigner,
 {
     // Build NIP98 Authorization header
     let payload: Sha256Hash = Sha256Hash::hash(&file_data);
-    let data = HttpData::new(desc.api_url.clone(), HttpMethod::POST).payload(payload);
-    let nip98_auth: String = data
-        .to_authorization(signer)
-        .await
-        .map_err(|e| UploadError::UploadError(e.to_string()))?;
+    let nip98_auth =
+        nip98_auth_header(signer, &desc.api_url, HttpMethod::POST, Some(payload)).await?;
 
     // Create shared counter for tracking upload progress
     let bytes_sent = Arc::new(Mutex::new(0u64));
@@ -301,10 +525,15 @@ where
 
     // Create form with tracking stream
     let file_part = {
-        let tracking_stream =
-            ProgressTrackingStream::new(file_data.clone(), bytes_sent.clone(), chunk_size);
+        let tracking_stream = ProgressTrackingStream::with_buffer_depth(
+            file_data.clone(),
+            bytes_sent.clone(),
+            params.chunk_size,
+            params.buffer_depth,
+        );
         let body = Body::wrap_stream(tracking_stream);
-        let mut part = Part::stream(body).file_name("filename");
+        let file_name = params.file_name.clone().unwrap_or_else(random_file_name);
+        let mut part = Part::stream(body).file_name(file_name);
 
         // Set MIME type if provided
         if let Some(mime_str) = mime_type {
@@ -329,9 +558,9 @@ where
     let mut last_percentage = 0;
     let mut poll_interval = tokio::time::interval(tokio::time::Duration::from_millis(100));
 
-    // Track stalled uploads
+    // Track stalled uploads by the time since the byte count last moved
     let mut last_bytes_sent = 0u64;
-    let mut stall_counter = 0;
+    let mut last_progress_at = std::time::Instant::now();
 
     // Use tokio::select to concurrently wait for the response and report progress
     let response = loop {
@@ -351,13 +580,12 @@ where
 
                 // Check if upload is stalled
                 if current_bytes == last_bytes_sent && percentage < 100 && percentage > 0 {
-                    stall_counter += 1;
-                    if stall_counter >= config.stall_threshold {
+                    if last_progress_at.elapsed() >= config.stall_timeout {
                         return Err(UploadError::UploadError("Upload stalled - no progress detected".to_string()));
                     }
                 } else {
-                    // Progress detected, reset stall counter
-                    stall_counter = 0;
+                    // Progress detected, reset the stall clock
+                    last_progress_at = std::time::Instant::now();
                     last_bytes_sent = current_bytes;
                 }
 
@@ -385,8 +613,13 @@ where
 
     // Extract url
     let nip94_event = res.nip94_event.ok_or(UploadError::ResponseDecodeError)?;
-    match nip94_event.tags.find_standardized(TagKind::Url) {
-        Some(TagStandard::Url(url)) => Ok(url.clone()),
-        _ => Err(UploadError::ResponseDecodeError),
-    }
+    let url = match nip94_event.tags.find_standardized(TagKind::Url) {
+        Some(TagStandard::Url(url)) => url.clone(),
+        _ => return Err(UploadError::ResponseDecodeError),
+    };
+
+    Ok(UploadResult {
+        url,
+        nip94_tags: nip94_event.tags,
+    })
 }
@@ -0,0 +1,23 @@
+use vector_sdk::client::{ClientConfig, DEFAULT_RELAYS, DEFAULT_TOR_PROXY};
+
+#[test]
+fn default_config_uses_no_proxy() {
+    assert_eq!(ClientConfig::default().proxy_addr, None);
+    assert_eq!(ClientConfig::builder().build().proxy_addr, None);
+    assert_eq!(ClientConfig::default().default_relays, DEFAULT_RELAYS);
+
+    let config = ClientConfig::builder().proxy(DEFAULT_TOR_PROXY).build();
+    assert_eq!(config.proxy_addr, Some(DEFAULT_TOR_PROXY));
+}
+
+#[test]
+fn builder_sets_read_and_write_relays() {
+    let config = ClientConfig::builder()
+        .read_relay("wss://read.example.com")
+        .write_relay("wss://write.example.com")
+        .build();
+
+    assert_eq!(config.read_relays, ["wss://read.example.com"]);
+    assert_eq!(config.write_relays, ["wss://write.example.com"]);
+    assert!(config.default_relays.is_empty());
+}
@@ -0,0 +1,88 @@
+use nostr_sdk::prelude::*;
+use vector_sdk::crypto::{encrypt_data, generate_encryption_params};
+use vector_sdk::message::FileMessage;
+use vector_sdk::{
+    calculate_file_hash, verify_attachment, AttachmentFile, CompressionAlgorithm,
+    MAX_DECOMPRESSED_SIZE,
+};
+
+/// Builds a file message for `compressed` bytes encrypted with fresh params.
+fn encrypted_file(compressed: &[u8]) -> (FileMessage, Vec<u8>) {
+    let params = generate_encryption_params().unwrap();
+    let data = encrypt_data(compressed, &params).unwrap();
+    let file = FileMessage {
+        sender: Keys::generate().public_key(),
+        event_id: EventId::all_zeros(),
+        url: "https://example.com/file".into(),
+        mime_type: None,
+        size: Some(data.len() as u64),
+        hash: None,
+        params: Some(params),
+        aad: None,
+        blurhash: None,
+        dimensions: None,
+        caption: None,
+        compression: Some(CompressionAlgorithm::Deflate.tag_value().to_string()),
+        voice_duration: None,
+        sensitive: false,
+        created_at: Timestamp::now(),
+    };
+    (file, data)
+}
+
+#[test]
+fn compression_never_grows_a_file() {
+    // Too small to shrink, and a PNG that sniffs as already compressed
+    let tiny = AttachmentFile::from_bytes(b"hi".to_vec());
+    let png = AttachmentFile::from_bytes(b"\x89PNG\r\n\x1a\n0000000000000000".to_vec());
+    for file in [tiny, png] {
+        let original = file.bytes.clone();
+        let file = file.compressed(CompressionAlgorithm::Deflate);
+        assert_eq!(file.compression, None);
+        assert_eq!(file.bytes, original);
+    }
+}
+
+#[test]
+fn compressed_file_round_trips() {
+    let text = "hello vector ".repeat(1000).into_bytes();
+    let file = AttachmentFile::from_bytes(text.clone()).compressed(CompressionAlgorithm::Deflate);
+    assert!(file.bytes.len() < text.len());
+
+    let (message, data) = encrypted_file(&file.bytes);
+    let plaintext = message.decrypt(&data).unwrap();
+    assert_eq!(plaintext, text);
+
+    // The ox hash describes the file as received, not the deflated bytes
+    let ox = file.original_hash.as_deref().unwrap();
+    assert_eq!(ox, calculate_file_hash(&text));
+    assert!(verify_attachment(&plaintext, ox));
+}
+
+#[test]
+fn compressed_image_is_stripped_as_plaintext() {
+    let pixels = ::image::RgbImage::from_pixel(32, 32, ::image::Rgb([10, 20, 30]));
+    let mut bmp = Vec::new();
+    pixels
+        .write_to(&mut std::io::Cursor::new(&mut bmp), ::image::ImageFormat::Bmp)
+        .unwrap();
+    let file = AttachmentFile::from_bytes(bmp).compressed(CompressionAlgorithm::Deflate);
+    assert!(file.compression.is_some());
+
+    let file = file.strip_metadata().unwrap();
+    assert!(file.compression.is_some());
+    let (message, data) = encrypted_file(&file.bytes);
+    let plaintext = message.decrypt(&data).unwrap();
+    assert!(::image::load_from_memory(&plaintext).is_ok());
+    assert!(verify_attachment(&plaintext, file.original_hash.as_deref().unwrap()));
+}
+
+#[test]
+fn oversized_decompression_is_refused() {
+    let bomb = AttachmentFile::from_bytes(vec![0u8; MAX_DECOMPRESSED_SIZE + 1])
+        .compressed(CompressionAlgorithm::Deflate);
+    assert!(bomb.bytes.len() < 1024 * 1024);
+
+    let (message, data) = encrypted_file(&bomb.bytes);
+    assert!(message.decrypt(&data).is_err());
+}
@@ -0,0 +1,48 @@
+use nostr_sdk::prelude::*;
+use vector_sdk::conversations::{ConversationIndex, MAX_CONVERSATIONS, MAX_UNREAD_PER_PEER};
+
+fn event_id(n: usize) -> EventId {
+    let mut bytes = [0u8; 32];
+    bytes[..8].copy_from_slice(&(n as u64).to_be_bytes());
+    EventId::from_byte_array(bytes)
+}
+
+#[test]
+fn unread_messages_are_capped_per_peer() {
+    let mut index = ConversationIndex::new();
+    let peer = Keys::generate().public_key();
+    let other = Keys::generate().public_key();
+
+    for n in 0..MAX_UNREAD_PER_PEER + 10 {
+        index.record_unread(peer, event_id(n));
+    }
+    index.record_unread(other, event_id(0));
+
+    let unread = index.unread(&peer);
+    assert_eq!(unread.len(), MAX_UNREAD_PER_PEER);
+    assert_eq!(unread.first(), Some(&event_id(10)));
+    assert_eq!(unread.last(), Some(&event_id(MAX_UNREAD_PER_PEER + 9)));
+    assert_eq!(index.unread(&other), [event_id(0)]);
+}
+
+#[test]
+fn oldest_conversations_are_evicted_past_the_cap() {
+    let mut index = ConversationIndex::new();
+    let peers: Vec<PublicKey> = (0..MAX_CONVERSATIONS + 5)
+        .map(|_| Keys::generate().public_key())
+        .collect();
+
+    for (n, peer) in peers.iter().enumerate() {
+        index.record(*peer, Timestamp::from_secs(1_000 + n as u64));
+        index.record_unread(*peer, event_id(n));
+    }
+
+    let conversations = index.list();
+    assert_eq!(conversations.len(), MAX_CONVERSATIONS);
+    for peer in &peers[..5] {
+        assert!(!conversations.iter().any(|(pubkey, _)| pubkey == peer));
+        assert!(index.unread(peer).is_empty());
+    }
+    assert_eq!(conversations[0].0, peers[MAX_CONVERSATIONS + 4]);
+    assert_eq!(index.unread(&peers[5]), [event_id(5)]);
+}
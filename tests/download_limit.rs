@@ -0,0 +1,54 @@
+use nostr_sdk::Url;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use vector_sdk::download::{download_data_with_limit, DownloadError};
+
+/// Serves one request with the given raw response head and body.
+fn serve_once(head: String, body: Vec<u8>) -> Url {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = Url::parse(&format!("http://{}/file", listener.local_addr().unwrap())).unwrap();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0u8; 1024];
+        let _ = stream.read(&mut request);
+        let _ = stream.write_all(head.as_bytes());
+        let _ = stream.write_all(&body);
+    });
+    url
+}
+
+#[tokio::test]
+async fn oversized_content_length_is_rejected() {
+    let url = serve_once(
+        "HTTP/1.1 200 OK\r\nContent-Length: 4096\r\nConnection: close\r\n\r\n".to_string(),
+        vec![0u8; 4096],
+    );
+
+    let result = download_data_with_limit(&url, None, 1024, Box::new(|_, _| Ok(()))).await;
+    assert!(matches!(result, Err(DownloadError::TooLarge(1024))), "{result:?}");
+}
+
+#[tokio::test]
+async fn oversized_body_without_length_is_rejected() {
+    // No Content-Length: the body runs until the connection closes
+    let url = serve_once(
+        "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n".to_string(),
+        vec![0u8; 4096],
+    );
+
+    let result = download_data_with_limit(&url, None, 1024, Box::new(|_, _| Ok(()))).await;
+    assert!(matches!(result, Err(DownloadError::TooLarge(1024))), "{result:?}");
+}
+
+#[tokio::test]
+async fn body_within_limit_is_returned() {
+    let url = serve_once(
+        "HTTP/1.1 200 OK\r\nContent-Length: 512\r\nConnection: close\r\n\r\n".to_string(),
+        vec![7u8; 512],
+    );
+
+    let data = download_data_with_limit(&url, None, 1024, Box::new(|_, _| Ok(())))
+        .await
+        .unwrap();
+    assert_eq!(data, vec![7u8; 512]);
+}
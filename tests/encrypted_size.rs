@@ -0,0 +1,11 @@
+use vector_sdk::crypto::{encrypt_data, encrypted_size, generate_encryption_params};
+
+#[test]
+fn encrypted_size_matches_encrypt_data() {
+    let params = generate_encryption_params().unwrap();
+    for len in [0, 1, 15, 16, 17, 4096, 65_537] {
+        let input = vec![0xa5u8; len];
+        let encrypted = encrypt_data(&input, &params).unwrap();
+        assert_eq!(encrypted.len(), encrypted_size(input.len()));
+    }
+}
@@ -0,0 +1,19 @@
+use vector_sdk::{calculate_file_hash, hash_file_path};
+
+#[test]
+fn streamed_hash_matches_in_memory_hash() {
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    let path = std::env::temp_dir().join(format!("vector-sdk-hash-{}", std::process::id()));
+    std::fs::write(&path, &data).unwrap();
+
+    let streamed = hash_file_path(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(streamed, calculate_file_hash(&data));
+}
+
+#[test]
+fn missing_file_is_an_error() {
+    let path = std::env::temp_dir().join("vector-sdk-hash-does-not-exist");
+    assert!(hash_file_path(&path).is_err());
+}
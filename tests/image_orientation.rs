@@ -0,0 +1,75 @@
+use image::{ImageFormat, Rgb, RgbImage};
+use vector_sdk::AttachmentFile;
+
+const WIDTH: u32 = 3;
+const HEIGHT: u32 = 2;
+
+/// Returns a distinct colour for every source pixel
+fn source_pixel(x: u32, y: u32) -> Rgb<u8> {
+    Rgb([x as u8 * 80, y as u8 * 120, 50])
+}
+
+/// Builds a PNG fixture whose eXIf chunk carries the given orientation
+fn png_with_orientation(orientation: u8) -> Vec<u8> {
+    let image = RgbImage::from_fn(WIDTH, HEIGHT, source_pixel);
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+        .unwrap();
+
+    // Big-endian TIFF header with a single IFD entry: Orientation (0x0112), SHORT
+    let exif = [
+        b'M', b'M', 0, 42, 0, 0, 0, 8, 0, 1, 0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, orientation, 0, 0,
+        0, 0, 0, 0,
+    ];
+    let mut chunk = (exif.len() as u32).to_be_bytes().to_vec();
+    chunk.extend_from_slice(b"eXIf");
+    chunk.extend_from_slice(&exif);
+    let mut crc = flate2::Crc::new();
+    crc.update(b"eXIf");
+    crc.update(&exif);
+    chunk.extend_from_slice(&crc.sum().to_be_bytes());
+
+    // Signature (8) + IHDR chunk (25)
+    png.splice(33..33, chunk);
+    png
+}
+
+#[test]
+fn strip_metadata_applies_every_exif_orientation() {
+    let (right, bottom) = (WIDTH - 1, HEIGHT - 1);
+    // Which source pixel ends up in the top-left corner, and whether the sides swap
+    let cases = [
+        (1, (0, 0), false),
+        (2, (right, 0), false),
+        (3, (right, bottom), false),
+        (4, (0, bottom), false),
+        (5, (0, 0), true),
+        (6, (0, bottom), true),
+        (7, (right, bottom), true),
+        (8, (right, 0), true),
+    ];
+
+    for (orientation, (x, y), swapped) in cases {
+        let file = AttachmentFile::from_bytes(png_with_orientation(orientation))
+            .strip_metadata()
+            .unwrap();
+        let upright = image::load_from_memory(&file.bytes).unwrap().to_rgb8();
+
+        let expected_size = if swapped { (HEIGHT, WIDTH) } else { (WIDTH, HEIGHT) };
+        assert_eq!(upright.dimensions(), expected_size, "orientation {orientation}");
+        assert_eq!(
+            *upright.get_pixel(0, 0),
+            source_pixel(x, y),
+            "orientation {orientation}"
+        );
+    }
+}
+
+#[test]
+fn stripped_image_has_no_orientation_left() {
+    let file = AttachmentFile::from_bytes(png_with_orientation(6))
+        .strip_metadata()
+        .unwrap();
+    assert!(!file.bytes.windows(4).any(|window| window == b"eXIf"));
+}
@@ -0,0 +1,32 @@
+use vector_sdk::VectorBot;
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("vector-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn encrypted_keys_round_trip() {
+    let path = temp_path("encrypted-key");
+    let keys = VectorBot::load_or_create_keys_with_passphrase(&path, "hunter2").unwrap();
+    assert!(std::fs::read_to_string(&path).unwrap().starts_with("ncryptsec1"));
+
+    let loaded = VectorBot::load_or_create_keys_with_passphrase(&path, "hunter2").unwrap();
+    assert_eq!(loaded.public_key(), keys.public_key());
+    assert!(VectorBot::load_or_create_keys_with_passphrase(&path, "wrong").is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn plain_key_is_refused_when_a_passphrase_is_given() {
+    let path = temp_path("plain-key");
+    let keys = VectorBot::load_or_create_keys(&path).unwrap();
+
+    assert!(VectorBot::load_or_create_keys_with_passphrase(&path, "hunter2").is_err());
+    let loaded = VectorBot::load_or_create_keys(&path).unwrap();
+    assert_eq!(loaded.public_key(), keys.public_key());
+
+    std::fs::remove_file(&path).unwrap();
+}
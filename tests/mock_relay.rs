@@ -0,0 +1,723 @@
+use futures_util::StreamExt;
+use nostr_sdk::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use vector_sdk::client::ClientConfig;
+use vector_sdk::message::DecodedMessage;
+use vector_sdk::mock::MockNip96Server;
+use vector_sdk::mock_relay::MockRelay;
+use vector_sdk::{
+    parse_receipt, parse_receipts, verify_attachment, AttachmentFile, CancellationToken,
+    CompressionAlgorithm, ReceiptKind, RelayStatus, VectorBot, VectorBotBuilder,
+};
+
+/// Points a bot at `relay`, builds it and connects it.
+async fn connect_bot(relay: &MockRelay, builder: VectorBotBuilder) -> VectorBot {
+    let bot = builder
+        .client_config(ClientConfig::builder().relay(relay.url()).build())
+        .build()
+        .await;
+    bot.client.connect().await;
+    bot.client.wait_for_connection(Duration::from_secs(5)).await;
+    bot.connect().await.unwrap();
+    bot
+}
+
+/// Builds a second bot on `relay` that only sends, without subscribing to
+/// its own gift wraps.
+///
+/// The relay does not echo a client's own events back to it, so tests that
+/// need the bot under test to receive something send it from here.
+async fn connect_sender(relay: &MockRelay, keys: Keys) -> VectorBot {
+    let sender = VectorBotBuilder::new(keys)
+        .client_config(ClientConfig::builder().relay(relay.url()).build())
+        .build()
+        .await;
+    sender.client.connect().await;
+    sender.client.wait_for_connection(Duration::from_secs(5)).await;
+    sender
+}
+
+/// A running [`VectorBot::run_message_loop`] that counts handled messages.
+struct CountingLoop {
+    received: Arc<AtomicUsize>,
+    shutdown: CancellationToken,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl CountingLoop {
+    /// Starts the message loop of `bot` in the background.
+    fn spawn(bot: &VectorBot) -> Self {
+        let received = Arc::new(AtomicUsize::new(0));
+        let shutdown = CancellationToken::new();
+
+        let loop_bot = bot.clone();
+        let loop_received = received.clone();
+        let loop_shutdown = shutdown.clone();
+        let handle = tokio::spawn(async move {
+            loop_bot
+                .run_message_loop(loop_shutdown, |_| {
+                    let received = loop_received.clone();
+                    async move {
+                        received.fetch_add(1, Ordering::SeqCst);
+                    }
+                })
+                .await
+        });
+
+        Self {
+            received,
+            shutdown,
+            handle,
+        }
+    }
+
+    /// Returns how many messages the loop has handled so far.
+    fn received(&self) -> usize {
+        self.received.load(Ordering::SeqCst)
+    }
+
+    /// Waits until the loop has handled at least `count` messages.
+    async fn wait_for(&self, count: usize) {
+        wait_until(Duration::from_secs(5), || self.received() >= count).await;
+    }
+
+    /// Stops the loop and waits for it to exit.
+    async fn stop(self) {
+        self.shutdown.cancel();
+        self.handle.await.unwrap();
+    }
+}
+
+/// Polls `condition` until it holds, failing the test after `timeout`.
+async fn wait_until(timeout: Duration, condition: impl Fn() -> bool) {
+    tokio::time::timeout(timeout, async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("condition not met in time");
+}
+
+/// Finds the gift wrap to `recipient` with the given content, if it was published.
+async fn find_message(relay: &MockRelay, recipient: &Keys, content: &str) -> Option<Event> {
+    for wrap in relay.published_events() {
+        if wrap.kind != Kind::GiftWrap {
+            continue;
+        }
+        if let Ok(gift) = UnwrappedGift::from_gift_wrap(recipient, &wrap).await {
+            if gift.rumor.content == content {
+                return Some(wrap);
+            }
+        }
+    }
+    None
+}
+
+#[tokio::test]
+async fn private_message_publishes_gift_wrap_for_recipient() {
+    let keys = Keys::generate();
+    let (bot, relay) = VectorBot::with_mock_client(keys.clone()).await.unwrap();
+    let recipient = Keys::generate();
+
+    let channel = bot.get_chat(recipient.public_key()).await;
+    assert!(channel.send_private_message("hello").await);
+
+    let wraps: Vec<Event> = relay
+        .published_events()
+        .into_iter()
+        .filter(|event| event.kind == Kind::GiftWrap)
+        .collect();
+    assert_eq!(wraps.len(), 1);
+    assert!(wraps[0].tags.public_keys().any(|pk| *pk == recipient.public_key()));
+
+    let unwrapped = UnwrappedGift::from_gift_wrap(&recipient, &wraps[0]).await.unwrap();
+    assert_eq!(unwrapped.sender, keys.public_key());
+    assert_eq!(unwrapped.rumor.kind, Kind::PrivateDirectMessage);
+    assert_eq!(unwrapped.rumor.content, "hello");
+}
+
+#[tokio::test]
+async fn bot_publishes_metadata_on_connect() {
+    let keys = Keys::generate();
+    let (_bot, relay) = VectorBot::with_mock_client(keys.clone()).await.unwrap();
+
+    assert!(relay
+        .published_events()
+        .iter()
+        .any(|event| event.kind == Kind::Metadata && event.pubkey == keys.public_key()));
+}
+
+#[tokio::test]
+async fn read_receipt_round_trips() {
+    let (bot, relay) = VectorBot::with_mock_client(Keys::generate()).await.unwrap();
+    let recipient = Keys::generate();
+    let message_id = EventId::all_zeros();
+
+    let channel = bot.get_chat(recipient.public_key()).await;
+    assert!(channel.send_receipt(message_id, ReceiptKind::Read).await);
+
+    let wrap = relay
+        .published_events()
+        .into_iter()
+        .find(|event| event.kind == Kind::GiftWrap)
+        .unwrap();
+    let unwrapped = UnwrappedGift::from_gift_wrap(&recipient, &wrap).await.unwrap();
+    assert_eq!(parse_receipt(&unwrapped.rumor), Some((message_id, ReceiptKind::Read)));
+}
+
+#[tokio::test]
+async fn send_result_reports_accepting_relay() {
+    let (bot, relay) = VectorBot::with_mock_client(Keys::generate()).await.unwrap();
+    let channel = bot.get_chat(Keys::generate().public_key()).await;
+
+    let result = channel.send_private_message_with_result("hello").await.unwrap();
+    assert_eq!(result.accepted_count(), 1);
+    assert!(result.failed_relays().is_empty());
+
+    let url = RelayUrl::parse(&relay.url()).unwrap();
+    assert_eq!(result.relays.get(&url), Some(&RelayStatus::Accepted));
+    assert!(relay
+        .published_events()
+        .iter()
+        .any(|event| Some(event.id) == result.gift_wrap_id));
+}
+
+#[tokio::test]
+async fn client_tag_is_added_to_metadata_but_not_dms() {
+    let relay = MockRelay::start().await.unwrap();
+    let keys = Keys::generate();
+    let bot = connect_bot(&relay, VectorBotBuilder::new(keys.clone()).client_tag("Vector")).await;
+
+    let recipient = Keys::generate();
+    let channel = bot.get_chat(recipient.public_key()).await;
+    assert!(channel.send_private_message("hello").await);
+
+    let events = relay.published_events();
+    let metadata = events.iter().find(|event| event.kind == Kind::Metadata).unwrap();
+    assert!(metadata.tags.find(TagKind::Client).is_some());
+
+    let wrap = events.iter().find(|event| event.kind == Kind::GiftWrap).unwrap();
+    let unwrapped = UnwrappedGift::from_gift_wrap(&recipient, wrap).await.unwrap();
+    assert!(unwrapped.rumor.tags.find(TagKind::Client).is_none());
+}
+
+#[tokio::test]
+async fn quote_carries_q_tag_and_nevent_reference() {
+    let (bot, relay) = VectorBot::with_mock_client(Keys::generate()).await.unwrap();
+    let recipient = Keys::generate();
+    let author = Keys::generate().public_key();
+    let quoted = EventId::all_zeros();
+
+    let channel = bot.get_chat(recipient.public_key()).await;
+    assert!(channel.send_quote(quoted, author, "look at this").await);
+
+    let wrap = relay
+        .published_events()
+        .into_iter()
+        .find(|event| event.kind == Kind::GiftWrap)
+        .unwrap();
+    let rumor = UnwrappedGift::from_gift_wrap(&recipient, &wrap).await.unwrap().rumor;
+    assert!(rumor.content.starts_with("look at this\n\nnostr:nevent1"));
+    let q_tag = rumor.tags.find_standardized(TagKind::q()).unwrap();
+    assert_eq!(
+        q_tag,
+        &TagStandard::Quote {
+            event_id: quoted,
+            relay_url: None,
+            public_key: Some(author),
+        }
+    );
+}
+
+#[tokio::test]
+async fn reaction_to_invalid_event_id_is_refused() {
+    let relay = MockRelay::start().await.unwrap();
+    let bot = connect_bot(&relay, VectorBotBuilder::new(Keys::generate())).await;
+    let channel = bot.get_chat(Keys::generate().public_key()).await;
+
+    assert!(!channel.send_reaction("not-an-id".into(), "+".into()).await);
+    let url = "https://example.com/party.png";
+    assert!(!channel.send_custom_reaction("not-an-id".into(), "party", url).await);
+    assert!(relay.published_events().iter().all(|event| event.kind != Kind::GiftWrap));
+}
+
+#[tokio::test]
+async fn oversized_gift_wraps_are_dropped() {
+    let keys = Keys::generate();
+    let (bot, relay) = VectorBot::with_mock_client(keys.clone()).await.unwrap();
+    let messages = CountingLoop::spawn(&bot);
+
+    let sender = connect_sender(&relay, Keys::generate()).await;
+    let channel = sender.get_chat(keys.public_key()).await;
+    assert!(channel.send_private_message("fits").await);
+    messages.wait_for(1).await;
+
+    bot.set_max_event_size(16);
+    assert!(channel.send_private_message("too big").await);
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(messages.received(), 1);
+
+    messages.stop().await;
+}
+
+/// Pages through a peer's history until the cursor runs out and returns
+/// every text and how many pages it took.
+async fn walk_history(bot: &VectorBot, peer: PublicKey, limit: usize) -> (Vec<String>, usize) {
+    let mut texts = Vec::new();
+    let mut pages = 0;
+    let mut cursor = Some(Timestamp::now() + 10);
+    while let Some(until) = cursor {
+        let (page, next) = bot.fetch_history(peer, until, limit).await.unwrap();
+        texts.extend(page.into_iter().map(|message| match message {
+            DecodedMessage::Text(text) => text.content,
+            other => panic!("unexpected message {other:?}"),
+        }));
+        pages += 1;
+        cursor = next;
+    }
+    texts.sort();
+    (texts, pages)
+}
+
+#[tokio::test]
+async fn fetch_history_returns_messages_from_peer() {
+    let keys = Keys::generate();
+    let (bot, relay) = VectorBot::with_mock_client(keys.clone()).await.unwrap();
+
+    let sender_keys = Keys::generate();
+    let sender = connect_sender(&relay, sender_keys.clone()).await;
+    let channel = sender.get_chat(keys.public_key()).await;
+    for text in ["one", "two", "three"] {
+        assert!(channel.send_private_message(text).await);
+    }
+    let stranger = Keys::generate().public_key();
+    assert!(bot.get_chat(stranger).await.send_private_message("unrelated").await);
+
+    let until = Timestamp::now() + 10;
+    let (all, cursor) = bot.fetch_history(sender_keys.public_key(), until, 10).await.unwrap();
+    assert!(cursor.is_none());
+    let mut texts: Vec<String> = all
+        .into_iter()
+        .map(|message| match message {
+            DecodedMessage::Text(text) => text.content,
+            other => panic!("unexpected message {other:?}"),
+        })
+        .collect();
+    texts.sort();
+    assert_eq!(texts, ["one", "three", "two"]);
+
+    let (empty, cursor) = bot.fetch_history(stranger, until, 10).await.unwrap();
+    assert!(empty.is_empty());
+    assert!(cursor.is_none());
+}
+
+#[tokio::test]
+async fn fetch_history_pages_return_every_message_once() {
+    let keys = Keys::generate();
+    let (bot, relay) = VectorBot::with_mock_client(keys.clone()).await.unwrap();
+
+    let sender_keys = Keys::generate();
+    let sender = connect_sender(&relay, sender_keys.clone()).await;
+    let channel = sender.get_chat(keys.public_key()).await;
+    let sent: Vec<String> = (0..12).map(|i| format!("message {i:02}")).collect();
+    for text in &sent {
+        assert!(channel.send_private_message(text).await);
+    }
+
+    // Another peer's wraps fill batches without adding to this history
+    let other = connect_sender(&relay, Keys::generate()).await;
+    let other_channel = other.get_chat(keys.public_key()).await;
+    for _ in 0..12 {
+        assert!(other_channel.send_private_message("noise").await);
+    }
+
+    let (texts, pages) = walk_history(&bot, sender_keys.public_key(), 1).await;
+    assert_eq!(texts, sent);
+    assert!(pages >= 2, "walked {pages} page(s)");
+}
+
+#[tokio::test]
+async fn fetch_history_keeps_same_second_wraps_together() {
+    let keys = Keys::generate();
+    let (bot, relay) = VectorBot::with_mock_client(keys.clone()).await.unwrap();
+
+    // Without a time window, every wrap is stamped with the current second
+    let sender_keys = Keys::generate();
+    let sender = VectorBotBuilder::new(sender_keys.clone())
+        .giftwrap_time_window(Duration::ZERO)
+        .client_config(ClientConfig::builder().relay(relay.url()).build())
+        .build()
+        .await;
+    sender.client.connect().await;
+    sender.client.wait_for_connection(Duration::from_secs(5)).await;
+    let channel = sender.get_chat(keys.public_key()).await;
+    let sent: Vec<String> = (0..6).map(|i| format!("burst {i}")).collect();
+    for text in &sent {
+        assert!(channel.send_private_message(text).await);
+    }
+
+    let (texts, _) = walk_history(&bot, sender_keys.public_key(), 1).await;
+    assert_eq!(texts, sent);
+}
+
+#[tokio::test]
+async fn cancelled_file_send_publishes_nothing() {
+    let (bot, relay) = VectorBot::with_mock_client(Keys::generate()).await.unwrap();
+    let channel = bot.get_chat(Keys::generate().public_key()).await;
+
+    let file = AttachmentFile::from_bytes(b"hello world".to_vec());
+    let (send, handle) = channel.send_private_file_cancellable(file);
+    handle.cancel();
+
+    assert!(!send.await);
+    assert!(handle.is_cancelled());
+    assert!(!relay
+        .published_events()
+        .iter()
+        .any(|event| event.kind == Kind::GiftWrap));
+}
+
+#[tokio::test]
+async fn file_send_cancelled_during_upload_publishes_nothing() {
+    let relay = MockRelay::start().await.unwrap();
+    let server = MockNip96Server::start().await.unwrap();
+    server.set_stall(true);
+    let builder = VectorBotBuilder::new(Keys::generate()).upload_server(server.server_config());
+    let bot = connect_bot(&relay, builder).await;
+    let channel = bot.get_chat(Keys::generate().public_key()).await;
+
+    let file = AttachmentFile::from_bytes(vec![7u8; 256 * 1024]);
+    let (send, handle) = channel.send_private_file_cancellable(file);
+    let cancel_mid_upload = async {
+        wait_until(Duration::from_secs(10), || server.request_count() == 1).await;
+        handle.cancel();
+    };
+    let (sent, ()) = tokio::join!(send, cancel_mid_upload);
+
+    assert!(!sent);
+    assert_eq!(server.request_count(), 1);
+    assert!(!relay
+        .published_events()
+        .iter()
+        .any(|event| event.kind == Kind::GiftWrap));
+}
+
+#[tokio::test]
+async fn compressed_file_ox_matches_plaintext() {
+    let relay = MockRelay::start().await.unwrap();
+    let server = MockNip96Server::start().await.unwrap();
+    let builder = VectorBotBuilder::new(Keys::generate()).upload_server(server.server_config());
+    let bot = connect_bot(&relay, builder).await;
+    let recipient = Keys::generate();
+    let channel = bot.get_chat(recipient.public_key()).await;
+
+    let text = "compress me ".repeat(500).into_bytes();
+    let file = AttachmentFile::from_bytes(text.clone()).compressed(CompressionAlgorithm::Deflate);
+    assert!(file.compression.is_some());
+    assert!(channel.send_private_file(Some(file)).await);
+
+    let mut ox = None;
+    for wrap in relay.published_events() {
+        if let Ok(gift) = UnwrappedGift::from_gift_wrap(&recipient, &wrap).await {
+            ox = gift
+                .rumor
+                .tags
+                .find(TagKind::custom("ox"))
+                .and_then(|tag| tag.content())
+                .map(str::to_string);
+        }
+    }
+    assert!(verify_attachment(&text, &ox.expect("file rumor with an ox tag")));
+}
+
+#[tokio::test]
+async fn invalid_voice_duration_is_refused_before_upload() {
+    let relay = MockRelay::start().await.unwrap();
+    let server = MockNip96Server::start().await.unwrap();
+    let builder = VectorBotBuilder::new(Keys::generate()).upload_server(server.server_config());
+    let bot = connect_bot(&relay, builder).await;
+    let channel = bot.get_chat(Keys::generate().public_key()).await;
+
+    for duration in [f32::NAN, f32::INFINITY, -1.0] {
+        let audio = AttachmentFile {
+            bytes: vec![0u8; 64],
+            img_meta: None,
+            extension: "ogg".into(),
+            compression: None,
+            original_hash: None,
+        };
+        assert!(!channel.send_voice_message(audio, duration).await);
+    }
+    assert_eq!(server.request_count(), 0);
+}
+
+#[tokio::test]
+async fn image_without_strippable_metadata_is_refused_before_upload() {
+    let relay = MockRelay::start().await.unwrap();
+    let server = MockNip96Server::start().await.unwrap();
+    let builder = VectorBotBuilder::new(Keys::generate()).upload_server(server.server_config());
+    let bot = connect_bot(&relay, builder).await;
+    let channel = bot.get_chat(Keys::generate().public_key()).await;
+
+    let mut heic = vec![0, 0, 0, 24];
+    heic.extend_from_slice(b"ftypheic\0\0\0\0mif1heic");
+    heic.extend_from_slice(&[0u8; 64]);
+    let photo = AttachmentFile {
+        bytes: heic,
+        img_meta: None,
+        extension: "heic".into(),
+        compression: None,
+        original_hash: None,
+    };
+    assert!(!channel.send_private_file(Some(photo)).await);
+    assert_eq!(server.request_count(), 0);
+}
+
+#[tokio::test]
+async fn subscribe_filter_streams_matching_events() {
+    let (bot, relay) = VectorBot::with_mock_client(Keys::generate()).await.unwrap();
+    let author = Keys::generate();
+    let filter = Filter::new().author(author.public_key()).kind(Kind::TextNote);
+    let id = bot.subscribe_filter(filter).await.unwrap();
+    let events = bot.subscription_events(id.clone());
+    tokio::pin!(events);
+
+    let publisher = connect_sender(&relay, author.clone()).await;
+    let sent = publisher.send_event(EventBuilder::text_note("gm")).await.unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(event.id, sent.val);
+    assert_eq!(event.content, "gm");
+
+    bot.unsubscribe(&id).await;
+}
+
+#[tokio::test]
+async fn mark_conversation_read_acknowledges_unread_messages() {
+    let keys = Keys::generate();
+    let (bot, relay) = VectorBot::with_mock_client(keys.clone()).await.unwrap();
+    let messages = CountingLoop::spawn(&bot);
+
+    let sender_keys = Keys::generate();
+    let sender = connect_sender(&relay, sender_keys.clone()).await;
+    let channel = sender.get_chat(keys.public_key()).await;
+    assert!(channel.send_private_message("one").await);
+    assert!(channel.send_private_message("two").await);
+    messages.wait_for(2).await;
+
+    assert_eq!(bot.mark_conversation_read(sender_keys.public_key()).await.unwrap(), 2);
+    assert_eq!(bot.mark_conversation_read(sender_keys.public_key()).await.unwrap(), 0);
+
+    let mut receipts = Vec::new();
+    for wrap in relay.published_events() {
+        if wrap.kind != Kind::GiftWrap {
+            continue;
+        }
+        if let Ok(gift) = UnwrappedGift::from_gift_wrap(&sender_keys, &wrap).await {
+            if gift.sender == keys.public_key() {
+                receipts.extend(parse_receipts(&gift.rumor));
+            }
+        }
+    }
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].0.len(), 2);
+    assert_eq!(receipts[0].1, ReceiptKind::Read);
+
+    messages.stop().await;
+}
+
+#[tokio::test]
+async fn rejected_send_is_not_listed_as_conversation() {
+    let relay = MockRelay::start().await.unwrap();
+    let bot = connect_bot(&relay, VectorBotBuilder::new(Keys::generate())).await;
+    let rejected = Keys::generate().public_key();
+    let accepted = Keys::generate().public_key();
+
+    relay.set_reject_events(true);
+    assert!(!bot.get_chat(rejected).await.send_private_message("lost").await);
+    relay.set_reject_events(false);
+    assert!(bot.get_chat(accepted).await.send_private_message("hi").await);
+
+    let peers: Vec<PublicKey> = bot.list_conversations().into_iter().map(|(pk, _)| pk).collect();
+    assert_eq!(peers, [accepted]);
+}
+
+#[tokio::test]
+async fn queued_send_reports_failure_and_is_replayed() {
+    let relay = MockRelay::start().await.unwrap();
+    let bot = connect_bot(&relay, VectorBotBuilder::new(Keys::generate()).outbox(true)).await;
+    let recipient = Keys::generate();
+    let channel = bot.get_chat(recipient.public_key()).await;
+
+    relay.set_reject_events(true);
+    assert!(!channel.send_private_message("queued").await);
+    assert_eq!(bot.pending_count(), 1);
+
+    let result = channel.send_private_message_with_result("also queued").await.unwrap();
+    assert!(result.is_queued());
+    assert!(!result.is_accepted());
+    assert!(result.relays.values().all(|status| *status == RelayStatus::Pending));
+    assert_eq!(bot.pending_count(), 2);
+
+    relay.set_reject_events(false);
+    wait_until(Duration::from_secs(15), || bot.pending_count() == 0).await;
+    assert!(find_message(&relay, &recipient, "queued").await.is_some());
+    assert!(find_message(&relay, &recipient, "also queued").await.is_some());
+}
+
+#[tokio::test]
+async fn persisted_outbox_holds_only_gift_wraps_and_replays() {
+    let relay = MockRelay::start().await.unwrap();
+    let path = std::env::temp_dir().join(format!("vector-outbox-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let recipient = Keys::generate();
+
+    let bot = connect_bot(&relay, VectorBotBuilder::new(Keys::generate()).outbox_path(&path)).await;
+    relay.set_reject_events(true);
+    let channel = bot.get_chat(recipient.public_key()).await;
+    assert!(!channel.send_private_message("top secret").await);
+    drop(channel);
+    drop(bot);
+
+    let stored = std::fs::read_to_string(&path).unwrap();
+    assert!(!stored.contains("top secret"));
+    assert!(stored.contains("\"kind\":1059"));
+
+    // A new bot picks the queued gift wrap up from disk and delivers it
+    let bot = connect_bot(&relay, VectorBotBuilder::new(Keys::generate()).outbox_path(&path)).await;
+    assert_eq!(bot.pending_count(), 1);
+    relay.set_reject_events(false);
+    wait_until(Duration::from_secs(15), || bot.pending_count() == 0).await;
+    assert!(find_message(&relay, &recipient, "top secret").await.is_some());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn silent_relay_does_not_hide_other_relays() {
+    let relay = MockRelay::start().await.unwrap();
+    let silent = MockRelay::start().await.unwrap();
+    silent.set_silent(true);
+
+    let publisher_keys = Keys::generate();
+    let publisher = connect_bot(&relay, VectorBotBuilder::new(publisher_keys.clone())).await;
+    publisher.set_status("out to lunch", None).await.unwrap();
+
+    let config = ClientConfig::builder().relay(relay.url()).relay(silent.url()).build();
+    let bot = VectorBotBuilder::new(Keys::generate())
+        .client_config(config)
+        .read_timeout(Duration::from_secs(2))
+        .build()
+        .await;
+    bot.client.connect().await;
+    bot.client.wait_for_connection(Duration::from_secs(5)).await;
+
+    let status = bot.fetch_status(publisher_keys.public_key()).await.unwrap();
+    assert_eq!(status.as_deref(), Some("out to lunch"));
+}
+
+#[tokio::test]
+async fn relay_hints_are_only_joined_for_one_send() {
+    let relay = MockRelay::start().await.unwrap();
+    let hint = MockRelay::start().await.unwrap();
+    let hint_url = RelayUrl::parse(&hint.url()).unwrap();
+    let recipient = Keys::generate();
+    let bot = connect_bot(&relay, VectorBotBuilder::new(Keys::generate())).await;
+    let channel = bot.get_chat(recipient.public_key()).await;
+
+    assert!(channel.send_private_message_to_relays("hinted", &[hint.url()]).await);
+    let wrap = find_message(&hint, &recipient, "hinted").await.unwrap();
+    assert!(find_message(&relay, &recipient, "hinted").await.is_none());
+    assert!(!bot.client.relays().await.contains_key(&hint_url));
+
+    let confirmed = channel
+        .confirm_delivery(wrap.id, &[hint.url()], Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(confirmed);
+    assert!(!bot.client.relays().await.contains_key(&hint_url));
+}
+
+#[tokio::test]
+async fn concurrent_hinted_sends_share_the_hint() {
+    let relay = MockRelay::start().await.unwrap();
+    let hint = MockRelay::start().await.unwrap();
+    let recipient = Keys::generate();
+    let bot = connect_bot(&relay, VectorBotBuilder::new(Keys::generate())).await;
+    let channel = bot.get_chat(recipient.public_key()).await;
+    let hints = [hint.url()];
+
+    let hinted = (0..8).map(|i| {
+        let channel = &channel;
+        let hints = &hints;
+        async move {
+            let text = format!("hinted {i}");
+            channel.send_private_message_to_relays(&text, hints).await
+        }
+    });
+    let pooled = (0..8).map(|i| {
+        let channel = &channel;
+        async move { channel.send_private_message(&format!("pooled {i}")).await }
+    });
+    let (hinted, pooled) = tokio::join!(
+        futures_util::future::join_all(hinted),
+        futures_util::future::join_all(pooled)
+    );
+    assert!(hinted.into_iter().all(|sent| sent));
+    assert!(pooled.into_iter().all(|sent| sent));
+
+    for i in 0..8 {
+        assert!(find_message(&hint, &recipient, &format!("hinted {i}")).await.is_some());
+        assert!(find_message(&hint, &recipient, &format!("pooled {i}")).await.is_none());
+        assert!(find_message(&relay, &recipient, &format!("hinted {i}")).await.is_none());
+    }
+    let hint_url = RelayUrl::parse(&hint.url()).unwrap();
+    assert!(!bot.client.relays().await.contains_key(&hint_url));
+}
+
+#[tokio::test]
+async fn confirm_delivery_checks_every_own_relay() {
+    let relay = MockRelay::start().await.unwrap();
+    let other = MockRelay::start().await.unwrap();
+    let recipient = Keys::generate();
+    let config = ClientConfig::builder().relays([relay.url(), other.url()]).build();
+    let bot = VectorBotBuilder::new(Keys::generate()).client_config(config).build().await;
+    bot.client.connect().await;
+    bot.client.wait_for_connection(Duration::from_secs(5)).await;
+    let channel = bot.get_chat(recipient.public_key()).await;
+
+    assert!(channel.send_private_message("everywhere").await);
+    let wrap = find_message(&relay, &recipient, "everywhere").await.unwrap();
+    let timeout = Duration::from_secs(2);
+    assert!(channel.confirm_delivery(wrap.id, &[], timeout).await.unwrap());
+
+    // One relay answering is not enough once another stops serving the event
+    other.set_silent(true);
+    assert!(!channel.confirm_delivery(wrap.id, &[], timeout).await.unwrap());
+}
+
+#[tokio::test]
+async fn rate_limit_spaces_out_publishes() {
+    let relay = MockRelay::start().await.unwrap();
+    let bot = connect_bot(&relay, VectorBotBuilder::new(Keys::generate()).rate_limit(2.0)).await;
+    let channel = bot.get_chat(Keys::generate().public_key()).await;
+
+    // The bucket holds one second of sends, after which they come every 500ms
+    let start = std::time::Instant::now();
+    let mut sent_at = Vec::new();
+    for i in 0..5 {
+        assert!(channel.send_private_message(&format!("message {i}")).await);
+        sent_at.push(start.elapsed());
+    }
+
+    assert!(sent_at[4] >= Duration::from_millis(1400), "{sent_at:?}");
+    for gap in sent_at[2..].windows(2).map(|w| w[1] - w[0]) {
+        assert!(gap >= Duration::from_millis(450), "{sent_at:?}");
+    }
+}
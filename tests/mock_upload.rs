@@ -0,0 +1,179 @@
+use futures_util::StreamExt;
+use nostr_sdk::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use vector_sdk::mock::MockNip96Server;
+use vector_sdk::upload::{
+    upload_data_with_progress, ProgressCallback, ProgressTrackingStream, UploadConfig, UploadError,
+    UploadParams,
+};
+
+/// Returns a progress callback that records every reported percentage
+fn recording_callback() -> (ProgressCallback, Arc<Mutex<Vec<u8>>>) {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    let callback: ProgressCallback = Box::new(move |percentage, _bytes| {
+        if let Some(p) = percentage {
+            sink.lock().unwrap().push(p);
+        }
+        Ok(())
+    });
+    (callback, seen)
+}
+
+fn fast_retries(retry_count: u32) -> UploadParams {
+    UploadParams {
+        retry_count,
+        retry_spacing: Duration::from_millis(10),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn upload_reports_increasing_progress() {
+    let server = MockNip96Server::start().await.unwrap();
+    let (callback, seen) = recording_callback();
+
+    let url = upload_data_with_progress(
+        &Keys::generate(),
+        &server.server_config(),
+        vec![7u8; 4 * 1024 * 1024],
+        Some("application/octet-stream"),
+        None,
+        callback,
+        Some(fast_retries(0)),
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert!(url.as_str().starts_with(server.base_url().as_str()));
+    assert_eq!(server.request_count(), 1);
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.first(), Some(&0));
+    assert_eq!(seen.last(), Some(&100));
+    assert!(seen.windows(2).all(|w| w[0] <= w[1]), "{seen:?}");
+}
+
+#[tokio::test]
+async fn server_error_triggers_configured_retries() {
+    let server = MockNip96Server::start().await.unwrap();
+    server.fail_next(usize::MAX);
+    let (callback, _) = recording_callback();
+
+    let result = upload_data_with_progress(
+        &Keys::generate(),
+        &server.server_config(),
+        vec![1u8; 1024],
+        Some("application/octet-stream"),
+        None,
+        callback,
+        Some(fast_retries(2)),
+        None,
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert_eq!(server.request_count(), 3);
+}
+
+#[tokio::test]
+async fn upload_recovers_after_transient_failure() {
+    let server = MockNip96Server::start().await.unwrap();
+    server.fail_next(1);
+    let (callback, _) = recording_callback();
+
+    let result = upload_data_with_progress(
+        &Keys::generate(),
+        &server.server_config(),
+        vec![1u8; 1024],
+        Some("application/octet-stream"),
+        None,
+        callback,
+        Some(fast_retries(2)),
+        None,
+    )
+    .await;
+
+    assert!(result.is_ok());
+    assert_eq!(server.request_count(), 2);
+}
+
+#[tokio::test]
+async fn stalled_server_aborts_upload() {
+    let server = MockNip96Server::start().await.unwrap();
+    server.set_stall(true);
+    let (callback, _) = recording_callback();
+    let config = UploadConfig {
+        stall_timeout: Duration::from_secs(1),
+        ..Default::default()
+    };
+
+    let result = upload_data_with_progress(
+        &Keys::generate(),
+        &server.server_config(),
+        vec![0u8; 64 * 1024 * 1024],
+        Some("application/octet-stream"),
+        None,
+        callback,
+        Some(fast_retries(0)),
+        Some(config),
+    )
+    .await;
+
+    match result {
+        Err(UploadError::UploadError(msg)) => assert!(msg.contains("stalled"), "{msg}"),
+        other => panic!("expected a stall error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn buffer_depth_does_not_change_uploaded_bytes() {
+    let data: Vec<u8> = (0..100_003u32).map(|i| (i * 31 % 251) as u8).collect();
+    for depth in [1, 2, 8, 64] {
+        let bytes_sent = Arc::new(Mutex::new(0));
+        let stream = ProgressTrackingStream::with_buffer_depth(
+            data.clone(),
+            bytes_sent.clone(),
+            4096,
+            depth,
+        );
+        let chunks: Vec<Vec<u8>> = stream.map(Result::unwrap).collect().await;
+
+        assert_eq!(chunks.concat(), data, "depth {depth}");
+        assert_eq!(*bytes_sent.lock().unwrap(), data.len() as u64);
+    }
+}
+
+#[tokio::test]
+async fn upload_goes_through_configured_proxy() {
+    let server = MockNip96Server::start().await.unwrap();
+    let proxy = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_addr = proxy.local_addr().unwrap();
+    let greeting = tokio::spawn(async move {
+        let (mut stream, _) = proxy.accept().await.unwrap();
+        let mut version = [0u8; 1];
+        stream.read_exact(&mut version).await.unwrap();
+        version[0]
+    });
+    let (callback, _) = recording_callback();
+
+    // The fake proxy hangs up after the greeting, so the upload fails
+    let result = upload_data_with_progress(
+        &Keys::generate(),
+        &server.server_config(),
+        vec![1u8; 1024],
+        Some("application/octet-stream"),
+        Some(proxy_addr),
+        callback,
+        Some(fast_retries(0)),
+        None,
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert_eq!(greeting.await.unwrap(), 0x05, "expected a SOCKS5 greeting");
+    assert_eq!(server.request_count(), 0);
+}
@@ -0,0 +1,25 @@
+use nostr_sdk::prelude::*;
+use vector_sdk::VectorBotBuilder;
+
+#[tokio::test]
+async fn npub_matches_keys() {
+    let keys = Keys::generate();
+    let bot = VectorBotBuilder::new(keys.clone()).build().await;
+    assert_eq!(PublicKey::from_bech32(&bot.npub()).unwrap(), keys.public_key());
+}
+
+#[tokio::test]
+async fn nprofile_carries_valid_relays() {
+    let keys = Keys::generate();
+    let bot = VectorBotBuilder::new(keys.clone()).build().await;
+    let relays = [
+        "wss://relay.damus.io".to_string(),
+        "not a relay".to_string(),
+        "wss://nos.lol".to_string(),
+    ];
+
+    let profile = Nip19Profile::from_bech32(&bot.nprofile(&relays)).unwrap();
+    assert_eq!(profile.public_key, keys.public_key());
+    let urls: Vec<String> = profile.relays.iter().map(|url| url.to_string()).collect();
+    assert_eq!(urls, ["wss://relay.damus.io", "wss://nos.lol"]);
+}
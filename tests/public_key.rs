@@ -0,0 +1,33 @@
+use nostr_sdk::prelude::*;
+use vector_sdk::{validate_public_key, Channel, VectorBotBuilder, VectorBotError};
+
+#[test]
+fn generated_key_is_valid() {
+    assert!(validate_public_key(&Keys::generate().public_key()).is_ok());
+}
+
+#[test]
+fn all_zero_key_is_rejected() {
+    let key = PublicKey::from_byte_array([0u8; 32]);
+    assert!(matches!(
+        validate_public_key(&key),
+        Err(VectorBotError::InvalidPublicKey(_))
+    ));
+}
+
+#[test]
+fn off_curve_key_is_rejected() {
+    let key = PublicKey::from_byte_array([0xff; 32]);
+    assert!(matches!(
+        validate_public_key(&key),
+        Err(VectorBotError::InvalidPublicKey(_))
+    ));
+}
+
+#[tokio::test]
+async fn channel_try_new_rejects_invalid_recipient() {
+    let bot = VectorBotBuilder::new(Keys::generate()).build().await;
+    let key = PublicKey::from_byte_array([0u8; 32]);
+    assert!(Channel::try_new(key, &bot).await.is_err());
+    assert!(Channel::try_new(Keys::generate().public_key(), &bot).await.is_ok());
+}
@@ -0,0 +1,18 @@
+use vector_sdk::ratelimit::{RateLimitError, RateLimiter};
+
+#[test]
+fn invalid_rates_are_rejected() {
+    for per_sec in [0.0, -1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        assert!(
+            matches!(RateLimiter::new(per_sec), Err(RateLimitError::InvalidRate(_))),
+            "{per_sec}"
+        );
+    }
+}
+
+#[test]
+fn valid_rates_are_kept() {
+    for per_sec in [0.5, 1.0, 20.0] {
+        assert_eq!(RateLimiter::new(per_sec).unwrap().per_sec(), per_sec);
+    }
+}
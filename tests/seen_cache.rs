@@ -0,0 +1,32 @@
+use nostr_sdk::prelude::*;
+use vector_sdk::dedup::SeenCache;
+
+fn event_id(n: u64) -> EventId {
+    let mut bytes = [0u8; 32];
+    bytes[..8].copy_from_slice(&n.to_be_bytes());
+    EventId::from_byte_array(bytes)
+}
+
+#[test]
+fn persisted_cache_is_compacted_and_reloaded() {
+    let path = std::env::temp_dir().join(format!("vector-seen-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut cache = SeenCache::with_persistence(3, &path).unwrap();
+    for n in 0..20 {
+        assert!(!cache.check_and_insert(event_id(n)));
+        let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+        assert!(lines <= 6, "{lines} lines after {n} inserts");
+    }
+    drop(cache);
+
+    let cache = SeenCache::with_persistence(3, &path).unwrap();
+    assert_eq!(cache.len(), 3);
+    for n in 17..20 {
+        assert!(cache.contains(&event_id(n)));
+    }
+    assert!(!cache.contains(&event_id(16)));
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+
+    std::fs::remove_file(&path).unwrap();
+}
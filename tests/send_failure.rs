@@ -0,0 +1,13 @@
+use nostr_sdk::prelude::*;
+use vector_sdk::VectorBot;
+
+#[tokio::test]
+async fn sends_with_no_relays_report_failure() {
+    let (bot, _relay) = VectorBot::with_mock_client(Keys::generate()).await.unwrap();
+    bot.client.force_remove_all_relays().await;
+    let channel = bot.get_chat(Keys::generate().public_key()).await;
+
+    assert!(!channel.send_private_message("nobody hears this").await);
+    assert!(!channel.send_reaction(EventId::all_zeros().to_hex(), "+".into()).await);
+    assert!(!channel.send_typing_indicator().await);
+}
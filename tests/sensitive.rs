@@ -0,0 +1,35 @@
+use nostr_sdk::prelude::*;
+use vector_sdk::is_sensitive;
+use vector_sdk::message::DecodedMessage;
+
+fn file_rumor(sender: &Keys, sensitive: bool) -> UnsignedEvent {
+    let mut builder = EventBuilder::new(Kind::from_u16(15), "https://example.com/file")
+        .tag(Tag::custom(TagKind::custom("file-type"), ["image/png"]));
+    if sensitive {
+        builder =
+            builder.tag(Tag::from_standardized(TagStandard::ContentWarning { reason: None }));
+    }
+    builder.build(sender.public_key())
+}
+
+#[test]
+fn content_warning_tag_marks_rumor_sensitive() {
+    let sender = Keys::generate();
+    assert!(is_sensitive(&file_rumor(&sender, true)));
+    assert!(!is_sensitive(&file_rumor(&sender, false)));
+}
+
+#[test]
+fn decoded_file_reports_sensitive() {
+    let sender = Keys::generate();
+    let receiver = Keys::generate();
+    let gift = UnwrappedGift {
+        sender: sender.public_key(),
+        rumor: file_rumor(&sender, true),
+    };
+
+    match DecodedMessage::decode(receiver.secret_key(), gift) {
+        DecodedMessage::File(file) => assert!(file.sensitive),
+        other => panic!("expected a file, got {other:?}"),
+    }
+}
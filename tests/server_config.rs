@@ -0,0 +1,36 @@
+use nostr_sdk::nips::nip96::ServerConfig;
+use vector_sdk::upload::{validate_server_config, UploadError};
+
+fn parse(json: &str) -> ServerConfig {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn minimal_config_is_accepted() {
+    let conf = parse(
+        r#"{"api_url": "https://files.example.com/upload",
+            "download_url": "https://files.example.com"}"#,
+    );
+    assert!(validate_server_config(&conf).is_ok());
+}
+
+#[test]
+fn placeholder_api_url_is_rejected() {
+    let conf = parse(
+        r#"{"api_url": "about:blank",
+            "download_url": "https://files.example.com"}"#,
+    );
+    let err = validate_server_config(&conf).unwrap_err();
+    assert!(matches!(err, UploadError::GenericError(ref msg) if msg.contains("api_url")));
+}
+
+#[test]
+fn delegated_config_names_the_delegate() {
+    let conf = parse(
+        r#"{"api_url": "data:,",
+            "download_url": "https://files.example.com",
+            "delegated_to_url": "https://other.example.com"}"#,
+    );
+    let err = validate_server_config(&conf).unwrap_err();
+    assert!(err.to_string().contains("https://other.example.com"));
+}
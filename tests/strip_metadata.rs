@@ -0,0 +1,76 @@
+use image::{ImageFormat, Rgb, RgbImage};
+use vector_sdk::{AttachmentFile, VectorBotError};
+
+/// Marker text placed in the EXIF payload of the fixtures
+const SECRET: &[u8] = b"GPS 52.5200N 13.4050E";
+
+/// Builds a JPEG whose APP1 segment carries [`SECRET`]
+fn jpeg_with_exif() -> Vec<u8> {
+    let image = RgbImage::from_pixel(4, 4, Rgb([200, 100, 50]));
+    let mut jpeg = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+        .unwrap();
+
+    let mut payload = b"Exif\0\0".to_vec();
+    payload.extend_from_slice(SECRET);
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+    segment.extend_from_slice(&payload);
+
+    // Right after the SOI marker
+    jpeg.splice(2..2, segment);
+    jpeg
+}
+
+/// Builds the start of an ISO-BMFF image with the given `ftyp` brand
+fn iso_bmff(brand: &[u8; 4]) -> Vec<u8> {
+    let mut bytes = vec![0, 0, 0, 24];
+    bytes.extend_from_slice(b"ftyp");
+    bytes.extend_from_slice(brand);
+    bytes.extend_from_slice(&[0, 0, 0, 0]);
+    bytes.extend_from_slice(b"mif1");
+    bytes.extend_from_slice(brand);
+    bytes.extend_from_slice(SECRET);
+    bytes
+}
+
+fn contains_secret(bytes: &[u8]) -> bool {
+    bytes.windows(SECRET.len()).any(|window| window == SECRET)
+}
+
+#[test]
+fn jpeg_exif_is_removed() {
+    let file = AttachmentFile::from_bytes(jpeg_with_exif()).strip_metadata().unwrap();
+    assert!(!contains_secret(&file.bytes));
+    assert!(image::load_from_memory(&file.bytes).is_ok());
+}
+
+#[test]
+fn truncated_jpeg_is_refused() {
+    // Cut off just before the end of the APP1 segment (SOI, marker, length,
+    // "Exif\0\0"), so neither rewriting nor decoding works
+    let mut jpeg = jpeg_with_exif();
+    jpeg.truncate(2 + 4 + 6 + SECRET.len() - 1);
+
+    let result = AttachmentFile::from_bytes(jpeg).strip_metadata();
+    assert!(matches!(result, Err(VectorBotError::MetadataStripError(_))));
+}
+
+#[test]
+fn heic_and_avif_are_refused() {
+    for (brand, extension) in [(b"heic", "heic"), (b"avif", "avif")] {
+        let file = AttachmentFile {
+            bytes: iso_bmff(brand),
+            img_meta: None,
+            extension: extension.into(),
+            compression: None,
+            original_hash: None,
+        };
+        let result = file.strip_metadata();
+        assert!(
+            matches!(result, Err(VectorBotError::MetadataStripError(_))),
+            "{extension}: {result:?}"
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use nostr_sdk::prelude::*;
+use vector_sdk::subscription::{SubscriptionConfig, SubscriptionError};
+
+#[test]
+fn default_config_does_not_panic() {
+    let config = SubscriptionConfig::default();
+    assert!(config.pubkey.is_none());
+    assert_eq!(config.kind, Kind::GiftWrap);
+}
+
+#[test]
+fn default_config_needs_a_pubkey_for_a_filter() {
+    assert!(matches!(
+        SubscriptionConfig::default().to_filter(),
+        Err(SubscriptionError::MissingPubkey)
+    ));
+}
+
+#[test]
+fn config_with_pubkey_builds_filter() {
+    let pubkey = Keys::generate().public_key();
+    let filter = SubscriptionConfig::for_pubkey(pubkey).to_filter().unwrap();
+    let event = EventBuilder::new(Kind::GiftWrap, "")
+        .tag(Tag::public_key(pubkey))
+        .sign_with_keys(&Keys::generate())
+        .unwrap();
+    assert!(filter.match_event(&event));
+}
@@ -0,0 +1,29 @@
+use vector_sdk::crypto::{decrypt_data, encrypt_data, generate_encryption_params};
+
+#[test]
+fn truncated_tags_round_trip() {
+    let input = b"attachment bytes";
+    for tag_size in 12..=16 {
+        let mut params = generate_encryption_params().unwrap();
+        params.tag_size = tag_size;
+
+        let encrypted = encrypt_data(input, &params).unwrap();
+        assert_eq!(encrypted.len(), input.len() + tag_size);
+        assert_eq!(decrypt_data(&encrypted, &params).unwrap(), input);
+    }
+}
+
+#[test]
+fn input_shorter_than_tag_is_rejected() {
+    let params = generate_encryption_params().unwrap();
+    let err = decrypt_data(&[0u8; 8], &params).unwrap_err();
+    assert!(err.to_string().contains("16-byte tag"));
+}
+
+#[test]
+fn unsupported_tag_size_is_rejected() {
+    let mut params = generate_encryption_params().unwrap();
+    params.tag_size = 8;
+    assert!(encrypt_data(b"data", &params).is_err());
+    assert!(decrypt_data(&[0u8; 32], &params).is_err());
+}
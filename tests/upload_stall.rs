@@ -0,0 +1,57 @@
+use nostr_sdk::prelude::*;
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+use vector_sdk::upload::{upload_data_with_progress, UploadConfig, UploadError, UploadParams};
+
+/// A server that accepts the connection but never reads the request body,
+/// so the upload fills the socket buffers and then makes no further progress.
+fn stalled_server() -> (TcpListener, Url) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = Url::parse(&format!("http://{}/upload", listener.local_addr().unwrap())).unwrap();
+    (listener, url)
+}
+
+#[tokio::test]
+async fn upload_fails_after_stall_timeout() {
+    let (listener, api_url) = stalled_server();
+    let accepted = std::thread::spawn(move || listener.accept().map(|(stream, _)| stream));
+
+    let desc: ServerConfig = serde_json::from_value(serde_json::json!({
+        "api_url": api_url,
+        "download_url": api_url,
+    }))
+    .unwrap();
+    let config = UploadConfig {
+        stall_timeout: Duration::from_secs(1),
+        ..Default::default()
+    };
+    let params = UploadParams {
+        retry_count: 0,
+        ..Default::default()
+    };
+
+    // Large enough that the body cannot fit in the loopback socket buffers
+    let data = vec![0u8; 64 * 1024 * 1024];
+    let started = Instant::now();
+    let result = upload_data_with_progress(
+        &Keys::generate(),
+        &desc,
+        data,
+        Some("application/octet-stream"),
+        None,
+        Box::new(|_, _| Ok(())),
+        Some(params),
+        Some(config),
+    )
+    .await;
+
+    let elapsed = started.elapsed();
+    match result {
+        Err(UploadError::UploadError(msg)) => assert!(msg.contains("stalled"), "{msg}"),
+        other => panic!("expected a stall error, got {other:?}"),
+    }
+    assert!(elapsed >= Duration::from_secs(1));
+    assert!(elapsed < Duration::from_secs(15));
+
+    drop(accepted.join());
+}